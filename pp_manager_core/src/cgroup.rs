@@ -0,0 +1,530 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+
+use crate::error::ManagerError;
+
+/// Where the unified cgroup hierarchy is normally mounted
+pub const DEFAULT_ROOT: &str = "/sys/fs/cgroup";
+
+// cpu.max period; quotas are expressed against it
+const CPU_MAX_PERIOD_US: u64 = 100_000;
+
+// Controllers the managed subtree hands down to its per-rule groups
+const CONTROLLERS: [&str; 4] = ["cpu", "cpuset", "io", "memory"];
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// cgroup v2 limits a rule applies to its processes; unset limits are left alone
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct CgroupLimits {
+    // CPU quota in cores' worth of time (cpu.max), e.g. 2.0 for two full cores
+    #[serde(default)]
+    pub cpu_max_cores: Option<f64>,
+    // Relative CPU share (cpu.weight), 1-10000 with 100 as the default
+    #[serde(default)]
+    pub cpu_weight: Option<u32>,
+    // Restrict the group to the rule's selected cores (cpuset.cpus)
+    #[serde(default)]
+    pub cpuset: bool,
+    // Relative I/O share (io.weight), 1-10000 with 100 as the default
+    #[serde(default)]
+    pub io_weight: Option<u32>,
+    // Memory usage above which the group is throttled and reclaimed (memory.high), in MiB
+    #[serde(default)]
+    pub memory_high_mb: Option<u64>,
+    // Hard memory limit; the OOM killer acts inside the group beyond it (memory.max), in MiB
+    #[serde(default)]
+    pub memory_max_mb: Option<u64>,
+}
+
+impl CgroupLimits {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(cores) = self.cpu_max_cores {
+            if !(cores > 0.0 && cores.is_finite()) {
+                return Err(format!("cgroup cpu_max_cores must be greater than 0, got {}", cores));
+            }
+        }
+        for (name, weight) in [("cpu_weight", self.cpu_weight), ("io_weight", self.io_weight)] {
+            if let Some(weight) = weight {
+                if !(1..=10000).contains(&weight) {
+                    return Err(format!("cgroup {} {} is out of range 1-10000", name, weight));
+                }
+            }
+        }
+        for (name, limit) in [("memory_high_mb", self.memory_high_mb), ("memory_max_mb", self.memory_max_mb)] {
+            if limit == Some(0) {
+                return Err(format!("cgroup {} must be greater than 0", name));
+            }
+            if limit.is_some_and(|limit| limit > u64::MAX / BYTES_PER_MB) {
+                return Err(format!("cgroup {} is above the largest limit of {} MiB", name, u64::MAX / BYTES_PER_MB));
+            }
+        }
+        if let (Some(high), Some(max)) = (self.memory_high_mb, self.memory_max_mb) {
+            if high > max {
+                return Err(format!("cgroup memory_high_mb {} is above memory_max_mb {}", high, max));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The memory limits of the group a process is in, as the kernel reports them ("max" when unlimited)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryLimits {
+    pub memory_high: Option<String>,
+    pub memory_max: Option<String>,
+}
+
+/// The manager's own cgroup v2 subtree, with one child group per rule
+#[derive(Debug, Clone)]
+pub struct CgroupTree {
+    // cgroup v2 mount point, or any directory standing in for it
+    root: PathBuf,
+    subtree: PathBuf,
+    // PID -> group the process was in before the manager moved it
+    original_groups: HashMap<u32, PathBuf>,
+}
+
+/// Classify a failed cgroup file operation: the kernel answers EACCES or EPERM when the
+/// hierarchy isn't writable by us, and ESRCH when a process being moved has exited
+fn cgroup_error(err: &io::Error, pid: Option<u32>, message: impl fmt::Display) -> ManagerError {
+    match pid {
+        Some(pid) if is_no_such_process(err) => ManagerError::process_exited(pid, format!("{}: process has exited", message)),
+        _ if err.kind() == io::ErrorKind::PermissionDenied => ManagerError::access_denied(
+            pid,
+            format!("{}: {}. Run as root or delegate the cgroup subtree.", message, err),
+        ),
+        _ => ManagerError::os(err, message),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn is_no_such_process(err: &io::Error) -> bool {
+    err.raw_os_error() == Some(libc::ESRCH)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_no_such_process(_err: &io::Error) -> bool {
+    false
+}
+
+/// Check that a subtree or group name is a single directory below its parent,
+/// so nothing the manager creates or removes can resolve to the root or outside it
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Err(format!("cgroup name '{}' must be a single directory name other than '.' or '..'", name));
+    }
+    Ok(())
+}
+
+/// The group a rule's processes are placed in: its name with anything but letters, digits,
+/// '.', '-' and '_' replaced by '_'
+pub fn group_name(rule_name: &str) -> Result<String, String> {
+    let group_name: String = rule_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    validate_name(&group_name).map_err(|e| format!("Rule '{}' can't have its own cgroup: {}", rule_name, e))?;
+    Ok(group_name)
+}
+
+impl CgroupTree {
+    pub fn new(root: &Path, subtree: &str) -> Result<Self, ManagerError> {
+        validate_name(subtree).map_err(ManagerError::invalid_config)?;
+        Ok(Self {
+            root: root.to_path_buf(),
+            subtree: root.join(subtree),
+            original_groups: HashMap::new(),
+        })
+    }
+
+    /// Create the managed subtree and enable the controllers per-rule groups need;
+    /// returns the controllers that could not be enabled
+    pub fn prepare(&self) -> Result<Vec<String>, ManagerError> {
+        fs::create_dir_all(&self.subtree)
+            .map_err(|e| cgroup_error(&e, None, format_args!("Failed to create cgroup {}", self.subtree.display())))?;
+
+        // Every level from the mount down to the subtree must delegate the controllers
+        let mut unavailable = Vec::new();
+        for controller in CONTROLLERS {
+            let enabled = self.subtree
+                .ancestors()
+                .take_while(|path| path.starts_with(&self.root))
+                .collect::<Vec<_>>()
+                .into_iter()
+                .rev()
+                .all(|path| fs::write(path.join("cgroup.subtree_control"), format!("+{}", controller)).is_ok());
+
+            if !enabled {
+                unavailable.push(controller.to_string());
+            }
+        }
+        Ok(unavailable)
+    }
+
+    fn group_path(&self, rule_name: &str) -> Result<PathBuf, ManagerError> {
+        Ok(self.subtree.join(group_name(rule_name).map_err(ManagerError::invalid_config)?))
+    }
+
+    /// Bring a rule's group in line with its limits, returning what was changed;
+    /// `memory_nodes` confines the group's memory to those NUMA nodes (cpuset.mems)
+    pub fn apply_limits(&self, rule_name: &str, limits: &CgroupLimits, affinity_mask: u64, memory_nodes: Option<u64>) -> Result<Vec<String>, ManagerError> {
+        let group = self.group_path(rule_name)?;
+        if !group.exists() {
+            fs::create_dir(&group).map_err(|e| cgroup_error(&e, None, format_args!("Failed to create cgroup {}", group.display())))?;
+        }
+
+        let mut changes = Vec::new();
+
+        if let Some(cores) = limits.cpu_max_cores {
+            let quota = (cores * CPU_MAX_PERIOD_US as f64).round() as u64;
+            let desired = format!("{} {}", quota, CPU_MAX_PERIOD_US);
+            if let Some(change) = write_if_changed(&group, "cpu.max", &desired, |current| current.to_string())? {
+                changes.push(change);
+            }
+        }
+
+        if let Some(weight) = limits.cpu_weight {
+            if let Some(change) = write_if_changed(&group, "cpu.weight", &weight.to_string(), |current| current.to_string())? {
+                changes.push(change);
+            }
+        }
+
+        if limits.cpuset {
+            // The kernel reports the list in its own range form, so compare in that form
+            if let Some(change) = write_if_changed(&group, "cpuset.cpus", &format_cpu_list(affinity_mask), |current| current.to_string())? {
+                changes.push(change);
+            }
+        }
+
+        if let Some(node_mask) = memory_nodes {
+            if let Some(change) = write_if_changed(&group, "cpuset.mems", &format_cpu_list(node_mask), |current| current.to_string())? {
+                changes.push(change);
+            }
+        }
+
+        if let Some(weight) = limits.io_weight {
+            // io.weight reads back as "default <weight>" followed by per-device overrides
+            let default_weight = |current: &str| {
+                current.lines().next().unwrap_or_default().trim_start_matches("default").trim().to_string()
+            };
+            if let Some(change) = write_if_changed(&group, "io.weight", &weight.to_string(), default_weight)? {
+                changes.push(change);
+            }
+        }
+
+        for (file, limit_mb) in [("memory.high", limits.memory_high_mb), ("memory.max", limits.memory_max_mb)] {
+            if let Some(limit_mb) = limit_mb {
+                let desired = limit_mb
+                    .checked_mul(BYTES_PER_MB)
+                    .ok_or_else(|| ManagerError::invalid_config(format!("cgroup {} of {} MiB is too large", file, limit_mb)))?
+                    .to_string();
+                if let Some(change) = write_if_changed(&group, file, &desired, |current| current.to_string())? {
+                    changes.push(change);
+                }
+            }
+        }
+
+        Ok(changes)
+    }
+
+    /// Freeze or thaw every process in a rule's group, children included (cgroup.freeze)
+    pub fn set_frozen(&self, rule_name: &str, frozen: bool) -> Result<(), ManagerError> {
+        let group = self.group_path(rule_name)?;
+        fs::write(group.join("cgroup.freeze"), if frozen { "1" } else { "0" })
+            .map_err(|e| cgroup_error(&e, None, format_args!("Failed to {} cgroup {}", if frozen { "freeze" } else { "thaw" }, group.display())))
+    }
+
+    /// Whether a process is currently in its rule's group
+    pub fn contains(&self, rule_name: &str, pid: u32) -> bool {
+        self.group_path(rule_name)
+            .is_ok_and(|group| current_group(pid).as_deref() == Some(self.relative_name(&group).as_str()))
+    }
+
+    /// The cgroup v2 mount point this tree lives under
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Move a process into its rule's group, returning the change if it wasn't there yet
+    pub fn add_process(&mut self, rule_name: &str, pid: u32) -> Result<Option<String>, ManagerError> {
+        let group = self.group_path(rule_name)?;
+        let current = current_group(pid);
+        let target = self.relative_name(&group);
+
+        if current.as_deref() == Some(target.as_str()) {
+            return Ok(None);
+        }
+
+        fs::write(group.join("cgroup.procs"), pid.to_string())
+            .map_err(|e| cgroup_error(&e, Some(pid), format_args!("Failed to move PID {} into cgroup {}", pid, group.display())))?;
+
+        // Remember where the process came from, unless it is only moving between managed groups
+        if let Some(current) = &current {
+            let original = self.root.join(current.trim_start_matches('/'));
+            if !original.starts_with(&self.subtree) {
+                self.original_groups.entry(pid).or_insert(original);
+            }
+        }
+
+        Ok(Some(format!("cgroup: {} → {}", current.unwrap_or_else(|| "?".to_string()), target)))
+    }
+
+    /// Move every process back to the group it came from and remove the managed subtree;
+    /// returns how many processes were moved back and what failed. A failure doesn't stop
+    /// the rest of the cleanup
+    pub fn cleanup(&mut self) -> (usize, Vec<ManagerError>) {
+        if !self.subtree.exists() {
+            return (0, Vec::new());
+        }
+
+        let mut moved = 0;
+        let mut errors = Vec::new();
+        let groups: Vec<PathBuf> = fs::read_dir(&self.subtree)
+            .map(|entries| entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).filter(|path| path.is_dir()).collect())
+            .unwrap_or_default();
+
+        for group in &groups {
+            // Children forked inside a group were never recorded; they go to the mount's root group
+            for pid in read_procs(group) {
+                let original = self.original_groups.get(&pid).filter(|path| path.is_dir()).unwrap_or(&self.root);
+                match fs::write(original.join("cgroup.procs"), pid.to_string()) {
+                    Ok(()) => moved += 1,
+                    Err(e) => errors.push(cgroup_error(&e, Some(pid), format_args!("Failed to move PID {} back to cgroup {}", pid, original.display()))),
+                }
+            }
+
+            // cgroupfs removes a group's control files along with it; anything else is left alone
+            if let Err(e) = fs::remove_dir(group) {
+                errors.push(cgroup_error(&e, None, format_args!("Failed to remove cgroup {}", group.display())));
+            }
+        }
+
+        self.original_groups.clear();
+        if let Err(e) = fs::remove_dir(&self.subtree) {
+            errors.push(cgroup_error(&e, None, format_args!("Failed to remove cgroup {}", self.subtree.display())));
+        }
+
+        (moved, errors)
+    }
+
+    /// A group's path as /proc/<pid>/cgroup shows it, relative to the mount point
+    fn relative_name(&self, group: &Path) -> String {
+        format!("/{}", group.strip_prefix(&self.root).unwrap_or(group).display())
+    }
+}
+
+/// Write a control file when its current value differs, describing the change
+fn write_if_changed(group: &Path, file: &str, desired: &str, normalize: impl Fn(&str) -> String) -> Result<Option<String>, ManagerError> {
+    let path = group.join(file);
+    let current = fs::read_to_string(&path).map(|value| normalize(value.trim())).unwrap_or_default();
+
+    if current == desired {
+        return Ok(None);
+    }
+
+    fs::write(&path, desired).map_err(|e| cgroup_error(&e, None, format_args!("Failed to write {}", path.display())))?;
+    let shown = if current.is_empty() { "unset" } else { current.as_str() };
+    Ok(Some(format!("{}: {} → {}", file, shown, desired)))
+}
+
+fn read_procs(group: &Path) -> Vec<u32> {
+    fs::read_to_string(group.join("cgroup.procs"))
+        .map(|procs| procs.lines().filter_map(|line| line.trim().parse().ok()).collect())
+        .unwrap_or_default()
+}
+
+/// The cgroup v2 group a process belongs to, e.g. "/pp_manager/icad.exe"
+pub fn current_group(pid: u32) -> Option<String> {
+    let cgroups = fs::read_to_string(format!("/proc/{}/cgroup", pid)).ok()?;
+    // The unified hierarchy is the entry with hierarchy ID 0
+    cgroups
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .map(|path| path.to_string())
+}
+
+/// Read the memory limits of the group a process currently belongs to
+pub fn memory_limits(root: &Path, pid: u32) -> Option<MemoryLimits> {
+    let group = root.join(current_group(pid)?.trim_start_matches('/'));
+    let read = |file: &str| fs::read_to_string(group.join(file)).ok().map(|value| value.trim().to_string());
+
+    Some(MemoryLimits {
+        memory_high: read("memory.high"),
+        memory_max: read("memory.max"),
+    })
+}
+
+/// Format a core or node mask the way cpuset.cpus and cpuset.mems report it, e.g. "0-3,6"
+fn format_cpu_list(mask: u64) -> String {
+    let mut ranges: Vec<String> = Vec::new();
+    let mut cpu = 0;
+
+    while cpu < 64 {
+        if mask & (1u64 << cpu) == 0 {
+            cpu += 1;
+            continue;
+        }
+
+        let start = cpu;
+        while cpu + 1 < 64 && mask & (1u64 << (cpu + 1)) != 0 {
+            cpu += 1;
+        }
+        ranges.push(if start == cpu { start.to_string() } else { format!("{}-{}", start, cpu) });
+        cpu += 1;
+    }
+
+    ranges.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn tree(root: &TempDir) -> CgroupTree {
+        CgroupTree::new(root.path(), "pp_manager").unwrap()
+    }
+
+    #[test]
+    fn rejects_names_outside_the_root() {
+        for name in ["", ".", "..", "/", "a/b", "../etc"] {
+            assert!(validate_name(name).is_err(), "{:?}", name);
+            assert!(CgroupTree::new(Path::new("/sys/fs/cgroup"), name).is_err(), "{:?}", name);
+        }
+        assert!(validate_name("pp_manager").is_ok());
+    }
+
+    #[test]
+    fn sanitises_rule_names() {
+        assert_eq!(group_name("icad.exe").unwrap(), "icad.exe");
+        assert_eq!(group_name("a b/c").unwrap(), "a_b_c");
+        assert!(group_name("..").is_err());
+        assert!(group_name(".").is_err());
+    }
+
+    #[test]
+    fn prepare_creates_subtree_and_delegates_controllers() {
+        let root = TempDir::new().unwrap();
+        let unavailable = tree(&root).prepare().unwrap();
+
+        assert!(unavailable.is_empty());
+        assert!(root.path().join("pp_manager").is_dir());
+        for level in [root.path().to_path_buf(), root.path().join("pp_manager")] {
+            assert!(level.join("cgroup.subtree_control").is_file());
+        }
+    }
+
+    #[test]
+    fn apply_limits_writes_only_changed_values() {
+        let root = TempDir::new().unwrap();
+        let tree = tree(&root);
+        tree.prepare().unwrap();
+        let limits = CgroupLimits {
+            cpu_max_cores: Some(1.5),
+            cpu_weight: Some(200),
+            cpuset: true,
+            io_weight: Some(50),
+            memory_high_mb: Some(1),
+            memory_max_mb: Some(2),
+        };
+
+        let changes = tree.apply_limits("icad.exe", &limits, 0b1011, Some(0b1)).unwrap();
+        assert_eq!(changes.len(), 7);
+
+        let group = root.path().join("pp_manager").join("icad.exe");
+        let read = |file: &str| fs::read_to_string(group.join(file)).unwrap();
+        assert_eq!(read("cpu.max"), "150000 100000");
+        assert_eq!(read("cpu.weight"), "200");
+        assert_eq!(read("cpuset.cpus"), "0-1,3");
+        assert_eq!(read("cpuset.mems"), "0");
+        assert_eq!(read("io.weight"), "50");
+        assert_eq!(read("memory.high"), "1048576");
+        assert_eq!(read("memory.max"), "2097152");
+
+        assert!(tree.apply_limits("icad.exe", &limits, 0b1011, Some(0b1)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_memory_limits_that_overflow_bytes() {
+        let largest = u64::MAX / BYTES_PER_MB;
+        let limits = |memory_max_mb| CgroupLimits { memory_max_mb: Some(memory_max_mb), ..CgroupLimits::default() };
+        assert!(limits(largest).validate().is_ok());
+        assert!(limits(largest + 1).validate().is_err());
+        assert!(limits(u64::MAX).validate().is_err());
+
+        // Limits that skipped validation fail instead of wrapping
+        let root = TempDir::new().unwrap();
+        let tree = tree(&root);
+        tree.prepare().unwrap();
+        let err = tree.apply_limits("icad.exe", &limits(u64::MAX), 0b1, None).unwrap_err();
+        assert!(matches!(err, ManagerError::InvalidConfig { .. }), "{:?}", err);
+    }
+
+    #[test]
+    fn add_process_and_cleanup_move_processes_back() {
+        let root = TempDir::new().unwrap();
+        let mut tree = tree(&root);
+        tree.prepare().unwrap();
+        tree.apply_limits("icad.exe", &CgroupLimits::default(), 0, None).unwrap();
+
+        let pid = std::process::id();
+        let change = tree.add_process("icad.exe", pid).unwrap();
+        assert!(change.unwrap().ends_with("/pp_manager/icad.exe"));
+        let group = root.path().join("pp_manager").join("icad.exe");
+        assert_eq!(read_procs(&group), vec![pid]);
+
+        // The stand-in group holds plain files, which only cgroupfs removes with the group:
+        // cleanup reports that and leaves them, and still removes the empty group
+        fs::create_dir(root.path().join("pp_manager").join("empty")).unwrap();
+        let (moved, errors) = tree.cleanup();
+
+        assert_eq!(moved, 1);
+        assert_eq!(read_procs(root.path()), vec![pid]);
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert!(group.join("cgroup.procs").is_file());
+        assert!(!root.path().join("pp_manager").join("empty").exists());
+    }
+
+    #[test]
+    fn cleanup_removes_empty_groups() {
+        let root = TempDir::new().unwrap();
+        let subtree = root.path().join("pp_manager");
+        fs::create_dir_all(subtree.join("icad.exe")).unwrap();
+
+        let (moved, errors) = tree(&root).cleanup();
+
+        assert_eq!(moved, 0);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(!subtree.exists());
+    }
+
+    #[test]
+    fn classifies_failed_writes() {
+        let denied = io::Error::from(io::ErrorKind::PermissionDenied);
+        assert!(cgroup_error(&denied, Some(42), "Failed to move PID 42").needs_elevation());
+
+        let other = io::Error::from(io::ErrorKind::InvalidInput);
+        assert!(matches!(cgroup_error(&other, Some(42), "Failed"), ManagerError::Os { .. }));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn reports_exited_processes() {
+        let gone = io::Error::from_raw_os_error(libc::ESRCH);
+        assert!(cgroup_error(&gone, Some(42), "Failed to move PID 42").is_process_exited());
+        assert!(!cgroup_error(&gone, None, "Failed to remove cgroup").is_process_exited());
+    }
+
+    #[test]
+    fn formats_cpu_lists_like_the_kernel() {
+        assert_eq!(format_cpu_list(0), "");
+        assert_eq!(format_cpu_list(0b1), "0");
+        assert_eq!(format_cpu_list(0b1111_0101), "0,2,4-7");
+        assert_eq!(format_cpu_list(u64::MAX), "0-63");
+    }
+}
@@ -0,0 +1,728 @@
+use std::collections::HashMap;
+use std::fs;
+use serde::{Serialize, Deserialize};
+
+use crate::cgroup::{self, CgroupLimits};
+use crate::error::ManagerError;
+use crate::logs::DEFAULT_LOG_CAPACITY;
+use crate::numa::{NumaConfig, NumaPlacement};
+use crate::platform;
+use crate::priority::{IoPriority, Priority, Scheduling, SchedulingPolicy};
+use crate::process_manager::{InstanceLimit, InstanceLimitAction, ProcessManager, ProcessRule, ThreadRule};
+
+// Read at startup and rewritten on every change, relative to the working directory
+pub const CONFIG_PATH: &str = "config.toml";
+// Where a config.toml that failed to load is kept before defaults can overwrite it
+pub const REJECTED_CONFIG_PATH: &str = "config.toml.bak";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProcessConfig {
+    pub name: String,
+    pub core_selections: Vec<bool>,
+    pub priority_class: Priority,
+    pub enabled: bool,
+    // Reserve the selected cores for this process: every other process is moved off them
+    #[serde(default)]
+    pub exclusive_cores: bool,
+    // Linux scheduling policy (SCHED_BATCH, SCHED_IDLE, SCHED_FIFO/RR); none leaves it untouched
+    #[serde(default)]
+    pub scheduling_policy: Option<SchedulingPolicy>,
+    // Real-time priority 1-99, required by the FIFO and round-robin policies
+    #[serde(default)]
+    pub rt_priority: Option<u8>,
+    // Realtime priority and FIFO/round-robin scheduling can hang the machine; they must be opted into
+    #[serde(default)]
+    pub realtime_acknowledged: bool,
+    // Linux I/O class and level (ionice); none leaves it untouched
+    #[serde(default)]
+    pub io_priority: Option<IoPriority>,
+    // Linux cgroup v2 limits; the rule's processes are moved into a managed group
+    #[serde(default)]
+    pub cgroup: Option<CgroupLimits>,
+    // Linux OOM killer preference, -1000 (never kill) to 1000 (kill first)
+    #[serde(default)]
+    pub oom_score_adj: Option<i32>,
+    // Linux NUMA memory placement; nodes default to those holding the selected cores
+    #[serde(default)]
+    pub numa: Option<NumaConfig>,
+    // Linux per-thread settings, matched against thread names
+    #[serde(default)]
+    pub thread_rules: Vec<ThreadRuleConfig>,
+    // Suspend this rule's processes while any of these processes is running
+    #[serde(default)]
+    pub suspend_while_running: Vec<String>,
+    // Instances allowed to run at once; extra ones, newest first, get extra_instance_action
+    #[serde(default)]
+    pub max_instances: Option<u32>,
+    #[serde(default)]
+    pub extra_instance_action: InstanceLimitAction,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ThreadRuleConfig {
+    // Thread name as in /proc/<pid>/task/<tid>/comm, or a prefix ending in '*'
+    pub thread_name: String,
+    // No selection leaves the thread's affinity untouched
+    #[serde(default)]
+    pub core_selections: Vec<bool>,
+    #[serde(default)]
+    pub priority_class: Option<Priority>,
+}
+
+impl ProcessConfig {
+    fn scheduling(&self) -> Result<Option<Scheduling>, String> {
+        self.scheduling_policy
+            .map(|policy| Scheduling::new(policy, self.rt_priority))
+            .transpose()
+            .map_err(|e| format!("Process '{}': {}", self.name, e))
+    }
+
+    fn numa_placement(&self) -> Result<Option<NumaPlacement>, String> {
+        let Some(numa) = &self.numa else {
+            return Ok(None);
+        };
+        if !platform::SUPPORTS_NUMA {
+            return Err(format!("Process '{}': NUMA placement is not supported on this platform", self.name));
+        }
+
+        numa.resolve(calculate_affinity_mask(&self.core_selections), &platform::numa_topology())
+            .map(Some)
+            .map_err(|e| format!("Process '{}': {}", self.name, e))
+    }
+
+    /// Validate the rule and resolve it against this machine
+    pub fn to_rule(&self) -> Result<ProcessRule, String> {
+        let rule = ProcessRule {
+            name: self.name.clone(),
+            affinity_mask: calculate_affinity_mask(&self.core_selections),
+            priority: self.priority_class,
+            scheduling: self.scheduling()?,
+            io_priority: self.io_priority
+                .map(IoPriority::validate)
+                .transpose()
+                .map_err(|e| format!("Process '{}': {}", self.name, e))?,
+            cgroup: self.cgroup.clone(),
+            oom_score_adj: self.oom_score_adj,
+            numa: self.numa_placement()?,
+            thread_rules: self.thread_rules
+                .iter()
+                .map(|thread_rule| ThreadRule {
+                    name_pattern: thread_rule.thread_name.clone(),
+                    affinity_mask: Some(calculate_affinity_mask(&thread_rule.core_selections)).filter(|&mask| mask != 0),
+                    priority: thread_rule.priority_class,
+                })
+                .collect(),
+            suspend_while_running: self.suspend_while_running.clone(),
+            instance_limit: self.max_instances.map(|max_instances| InstanceLimit {
+                max_instances,
+                action: self.extra_instance_action,
+            }),
+        };
+
+        if let Some(thread_rule) = self.thread_rules.iter().find(|thread_rule| thread_rule.thread_name.trim_end_matches('*').is_empty()) {
+            return Err(format!("Process '{}': thread rule '{}' needs a thread name", self.name, thread_rule.thread_name));
+        }
+
+        if let Some(oom_score_adj) = rule.oom_score_adj.filter(|adj| !(-1000..=1000).contains(adj)) {
+            return Err(format!("Process '{}': OOM score adjustment {} is out of range -1000 to 1000", self.name, oom_score_adj));
+        }
+
+        if self.max_instances == Some(0) {
+            return Err(format!("Process '{}': max_instances must be at least 1", self.name));
+        }
+
+        if let Some(limits) = &rule.cgroup {
+            limits.validate().map_err(|e| format!("Process '{}': {}", self.name, e))?;
+            cgroup::group_name(&self.name)?;
+        }
+
+        if rule.is_realtime() && !self.realtime_acknowledged {
+            return Err(format!(
+                "Process '{}': real-time settings can hang the system and need realtime_acknowledged = true",
+                self.name
+            ));
+        }
+        Ok(rule)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BackgroundDemotionConfig {
+    pub enabled: bool,
+    // Processes that keep their settings; everything else owned by the current user is demoted
+    pub allowlist: Vec<String>,
+    pub priority_class: Priority,
+    // Cores demoted processes are confined to; no selection leaves their affinity untouched
+    pub core_selections: Vec<bool>,
+}
+
+impl Default for BackgroundDemotionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allowlist: Vec::new(),
+            priority_class: Priority::BelowNormal,
+            core_selections: Vec::new(),
+        }
+    }
+}
+
+impl BackgroundDemotionConfig {
+    /// Demotion acts on every user process, so it may only lower priorities, and only onto cores that exist
+    pub fn validate(&self) -> Result<(), String> {
+        if matches!(self.priority_class, Priority::AboveNormal | Priority::High | Priority::Realtime) {
+            return Err(format!("Background demotion: processes cannot be demoted to {} priority", self.priority_class));
+        }
+
+        if let Some(core) = self.core_selections.iter().skip(64).position(|&selected| selected) {
+            return Err(format!("Background demotion: core {} is beyond the 64 cores an affinity mask covers", core + 64));
+        }
+        let unavailable = calculate_affinity_mask(&self.core_selections) & !ProcessManager::get_system_affinity_mask();
+        if unavailable != 0 {
+            return Err(format!("Background demotion: core {} is not available on this system", unavailable.trailing_zeros()));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CpuHogWatchdogConfig {
+    pub enabled: bool,
+    // CPU usage that counts as hogging, where 100 is one fully busy core
+    pub cpu_threshold_percent: f64,
+    // How long a process must stay above the threshold before it is demoted
+    pub sustained_seconds: u64,
+    // How long a demoted process must stay below the threshold before it is restored
+    pub calm_seconds: u64,
+    pub priority_class: Priority,
+    // Cores hogs are confined to; no selection leaves their affinity untouched
+    pub core_selections: Vec<bool>,
+    // Processes the watchdog never demotes
+    pub exemptions: Vec<String>,
+}
+
+impl Default for CpuHogWatchdogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cpu_threshold_percent: 80.0,
+            sustained_seconds: 30,
+            calm_seconds: 30,
+            priority_class: Priority::BelowNormal,
+            core_selections: Vec::new(),
+            exemptions: Vec::new(),
+        }
+    }
+}
+
+impl CpuHogWatchdogConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.cpu_threshold_percent > 0.0 && self.cpu_threshold_percent.is_finite()) {
+            return Err(format!("CPU hog watchdog: CPU threshold must be greater than 0%, got {}", self.cpu_threshold_percent));
+        }
+        if self.priority_class == Priority::Realtime {
+            return Err("CPU hog watchdog: hogs cannot be demoted to Realtime priority".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RealtimeGuardConfig {
+    // CPU usage that counts as a fully busy core, where 100 is one core
+    pub cpu_threshold_percent: f64,
+    // How long a real-time process may keep a core busy before it is demoted
+    pub max_full_core_seconds: u64,
+    // How long a demoted process must stay below the threshold before its real-time settings return
+    pub calm_seconds: u64,
+    // Priority demoted processes run at; real-time scheduling falls back to normal scheduling
+    pub priority_class: Priority,
+}
+
+impl Default for RealtimeGuardConfig {
+    fn default() -> Self {
+        Self {
+            cpu_threshold_percent: 95.0,
+            max_full_core_seconds: 10,
+            calm_seconds: 30,
+            priority_class: Priority::Normal,
+        }
+    }
+}
+
+impl RealtimeGuardConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.cpu_threshold_percent > 0.0 && self.cpu_threshold_percent.is_finite()) {
+            return Err(format!("Real-time guard: CPU threshold must be greater than 0%, got {}", self.cpu_threshold_percent));
+        }
+        if self.priority_class == Priority::Realtime {
+            return Err("The real-time guard cannot demote processes to Realtime priority".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CgroupConfig {
+    // cgroup v2 mount point; any directory can stand in for it when trying the feature out
+    pub root: String,
+    // Directory directly under the root the manager creates for its per-rule groups and removes on stop
+    pub subtree: String,
+}
+
+impl CgroupConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        cgroup::validate_name(&self.subtree).map_err(|e| format!("cgroups subtree: {}", e))
+    }
+}
+
+impl Default for CgroupConfig {
+    fn default() -> Self {
+        Self {
+            root: cgroup::DEFAULT_ROOT.to_string(),
+            subtree: "pp_manager".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct LogFileConfig {
+    pub enabled: bool,
+    // Defaults to the app's data directory
+    pub directory: Option<String>,
+    // A new file is started once the current one reaches this size, and every day
+    pub max_file_size_kb: u64,
+    // Older files are deleted
+    pub max_age_days: u32,
+}
+
+impl Default for LogFileConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            directory: None,
+            max_file_size_kb: 1024,
+            max_age_days: 30,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct LogThrottleConfig {
+    pub enabled: bool,
+    // Repeats of an entry within this long of the previous one are collapsed into it
+    pub repeat_window_seconds: u64,
+    // Entries about one rule logged per minute; the rest are counted instead
+    pub max_per_rule_per_minute: u32,
+    // How often something that keeps repeating, such as a failing rule, is logged again with its count
+    pub summary_interval_seconds: u64,
+}
+
+impl Default for LogThrottleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            repeat_window_seconds: 60,
+            max_per_rule_per_minute: 30,
+            summary_interval_seconds: 300,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Config {
+    // Legacy single process support (for backward compatibility)
+    pub target_process: String,
+    pub core_selections: Vec<bool>,
+    pub priority_class: Priority,
+
+    // New multi-process support
+    pub processes: Vec<ProcessConfig>,
+
+    // Processes that rules acting on every process (such as core reservation) never touch
+    #[serde(default = "default_protected_processes")]
+    pub protected_processes: Vec<String>,
+
+    // Catch-all rule demoting every process not on its allowlist
+    #[serde(default)]
+    pub background_demotion: BackgroundDemotionConfig,
+
+    // Automatic demotion of processes that keep using too much CPU
+    #[serde(default)]
+    pub cpu_hog_watchdog: CpuHogWatchdogConfig,
+
+    // Watchdog that demotes real-time rule processes monopolising a core
+    #[serde(default)]
+    pub realtime_guard: RealtimeGuardConfig,
+
+    // Where rules with cgroup limits get their managed groups
+    #[serde(default)]
+    pub cgroups: CgroupConfig,
+
+    // Log entries kept in memory; the oldest are dropped beyond this
+    #[serde(default = "default_log_capacity")]
+    pub log_capacity: usize,
+
+    // Every log entry is also appended to rotating JSON Lines files
+    #[serde(default)]
+    pub log_files: LogFileConfig,
+
+    // Suppression of repeated and excessive entries from the monitor
+    #[serde(default)]
+    pub log_throttle: LogThrottleConfig,
+}
+
+fn default_log_capacity() -> usize {
+    DEFAULT_LOG_CAPACITY
+}
+
+/// Processes the system can't run without; catch-all actions such as the core reservation skip them
+#[cfg(target_os = "linux")]
+fn default_protected_processes() -> Vec<String> {
+    [
+        "systemd",
+        "init",
+        "systemd-journald",
+        "systemd-logind",
+        "systemd-udevd",
+        "systemd-resolved",
+        "systemd-networkd",
+        "udevd",
+        "dbus-daemon",
+        "dbus-broker",
+        "sshd",
+        "polkitd",
+        "NetworkManager",
+        "Xorg",
+        "Xwayland",
+        "gnome-shell",
+        "kwin_wayland",
+        "kwin_x11",
+        "gdm",
+        "gdm3",
+        "sddm",
+        "lightdm",
+        "pipewire",
+        "wireplumber",
+        "pulseaudio",
+    ]
+    .iter()
+    .map(|name| name.to_string())
+    .collect()
+}
+
+/// Processes the system can't run without; catch-all actions such as the core reservation skip them
+#[cfg(not(target_os = "linux"))]
+fn default_protected_processes() -> Vec<String> {
+    [
+        "System",
+        "Registry",
+        "smss.exe",
+        "csrss.exe",
+        "wininit.exe",
+        "winlogon.exe",
+        "services.exe",
+        "lsass.exe",
+        "svchost.exe",
+        "dwm.exe",
+        "MsMpEng.exe",
+    ]
+    .iter()
+    .map(|name| name.to_string())
+    .collect()
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let cpu_count = ProcessManager::get_system_cpu_count() as usize;
+        let max_cores = cpu_count.max(8); // Ensure at least 8 cores for compatibility
+
+        // Initialize with performance cores selected (typically cores 4-5 on hybrid CPUs)
+        let mut core_selections = vec![false; max_cores];
+        if max_cores > 4 {
+            core_selections[4] = true;
+        }
+        if max_cores > 5 {
+            core_selections[5] = true;
+        }
+
+        Self {
+            target_process: "icad.exe".to_string(),
+            core_selections: core_selections.clone(),
+            priority_class: Priority::AboveNormal,
+            processes: vec![
+                ProcessConfig {
+                    name: "icad.exe".to_string(),
+                    core_selections,
+                    priority_class: Priority::AboveNormal,
+                    enabled: true,
+                    exclusive_cores: false,
+                    scheduling_policy: None,
+                    rt_priority: None,
+                    realtime_acknowledged: false,
+                    io_priority: None,
+                    cgroup: None,
+                    oom_score_adj: None,
+                    numa: None,
+                    thread_rules: Vec::new(),
+                    suspend_while_running: Vec::new(),
+                    max_instances: None,
+                    extra_instance_action: InstanceLimitAction::Warn,
+                }
+            ],
+            protected_processes: default_protected_processes(),
+            background_demotion: BackgroundDemotionConfig::default(),
+            cpu_hog_watchdog: CpuHogWatchdogConfig::default(),
+            realtime_guard: RealtimeGuardConfig::default(),
+            cgroups: CgroupConfig::default(),
+            log_capacity: DEFAULT_LOG_CAPACITY,
+            log_files: LogFileConfig::default(),
+            log_throttle: LogThrottleConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Reject settings that can't be applied as written, before they are used or saved
+    pub fn validate(&self) -> Result<(), String> {
+        for process_config in &self.processes {
+            process_config.to_rule()?;
+        }
+        self.background_demotion.validate()?;
+        self.cpu_hog_watchdog.validate()?;
+        self.realtime_guard.validate()?;
+        self.cgroups.validate()?;
+
+        // Rule names are sanitised into group names, so different names can map to one group
+        let mut groups: HashMap<String, &str> = HashMap::new();
+        for process_config in self.processes.iter().filter(|p| p.cgroup.is_some()) {
+            let group = cgroup::group_name(&process_config.name)?;
+            if let Some(other) = groups.insert(group.clone(), &process_config.name) {
+                return Err(format!("Processes '{}' and '{}' would share cgroup '{}'", other, process_config.name, group));
+            }
+        }
+        Ok(())
+    }
+
+    /// Rules of the enabled process configs, refusing settings this platform can't apply
+    pub fn enabled_rules(&self) -> Result<Vec<ProcessRule>, String> {
+        let rules: Vec<ProcessRule> = self.processes
+            .iter()
+            .filter(|p| p.enabled)
+            .map(|p| p.to_rule())
+            .collect::<Result<_, _>>()?;
+
+        if !platform::SUPPORTS_SCHEDULING_POLICY && rules.iter().any(|r| r.scheduling.is_some()) {
+            return Err("Scheduling policies are not supported on this platform".to_string());
+        }
+        if !platform::SUPPORTS_IO_PRIORITY && rules.iter().any(|r| r.io_priority.is_some()) {
+            return Err("I/O priorities are not supported on this platform".to_string());
+        }
+        if !platform::SUPPORTS_CGROUPS && rules.iter().any(|r| r.cgroup.is_some()) {
+            return Err("cgroups are not supported on this platform".to_string());
+        }
+        if !platform::SUPPORTS_THREAD_RULES && rules.iter().any(|r| !r.thread_rules.is_empty()) {
+            return Err("Thread rules are not supported on this platform".to_string());
+        }
+        if !platform::SUPPORTS_OOM_SCORE_ADJ && rules.iter().any(|r| r.oom_score_adj.is_some()) {
+            return Err("OOM score adjustment is not supported on this platform".to_string());
+        }
+
+        Ok(rules)
+    }
+}
+
+pub fn load_config() -> Result<Config, ManagerError> {
+    // A missing file means first run; a file that doesn't parse (e.g. an unknown priority) is rejected
+    let mut config: Config = match fs::read_to_string(CONFIG_PATH) {
+        Ok(data) => toml::from_str(&data).map_err(|e| ManagerError::invalid_config(format!("Invalid config.toml: {}", e)))?,
+        Err(_) => Config::default(),
+    };
+
+    // Ensure core_selections vector matches current system CPU count
+    let cpu_count = ProcessManager::get_system_cpu_count() as usize;
+    let max_cores = cpu_count.max(8); // Ensure at least 8 cores for compatibility
+
+    if config.core_selections.len() != max_cores {
+        // Resize the vector, preserving existing selections where possible
+        config.core_selections.resize(max_cores, false);
+    }
+
+    // Migrate legacy single process config to new multi-process format if needed
+    if config.processes.is_empty() && !config.target_process.is_empty() {
+        config.processes.push(ProcessConfig {
+            name: config.target_process.clone(),
+            core_selections: config.core_selections.clone(),
+            priority_class: config.priority_class,
+            enabled: true,
+            exclusive_cores: false,
+            scheduling_policy: None,
+            rt_priority: None,
+            realtime_acknowledged: false,
+            io_priority: None,
+            cgroup: None,
+            oom_score_adj: None,
+            numa: None,
+            thread_rules: Vec::new(),
+            suspend_while_running: Vec::new(),
+            max_instances: None,
+            extra_instance_action: InstanceLimitAction::Warn,
+        });
+    }
+
+    // Ensure all process configs have correct core count
+    for process_config in &mut config.processes {
+        if process_config.core_selections.len() != max_cores {
+            process_config.core_selections.resize(max_cores, false);
+        }
+        for thread_rule in &mut process_config.thread_rules {
+            if !thread_rule.core_selections.is_empty() && thread_rule.core_selections.len() != max_cores {
+                thread_rule.core_selections.resize(max_cores, false);
+            }
+        }
+    }
+
+    // An empty optional core selection means "leave affinity alone", so only resize real selections
+    for optional_cores in [
+        &mut config.background_demotion.core_selections,
+        &mut config.cpu_hog_watchdog.core_selections,
+    ] {
+        if !optional_cores.is_empty() && optional_cores.len() != max_cores {
+            optional_cores.resize(max_cores, false);
+        }
+    }
+
+    // Reject bad RT priorities, unacknowledged real-time settings and unusable cgroup names
+    config.validate().map_err(ManagerError::invalid_config)?;
+    Ok(config)
+}
+
+/// Cores reserved by enabled rules marked as exclusive
+pub fn reserved_core_mask(config: &Config) -> u64 {
+    config.processes
+        .iter()
+        .filter(|p| p.enabled && p.exclusive_cores)
+        .fold(0u64, |acc, p| acc | calculate_affinity_mask(&p.core_selections))
+}
+
+/// Names of processes managed by enabled rules, which keep their own settings
+pub fn enabled_process_names(config: &Config) -> Vec<String> {
+    config.processes
+        .iter()
+        .filter(|p| p.enabled)
+        .map(|p| p.name.clone())
+        .collect()
+}
+
+pub fn calculate_affinity_mask(core_selections: &[bool]) -> u64 {
+    core_selections
+        .iter()
+        .enumerate()
+        .fold(0u64, |acc, (i, &selected)| {
+            if selected { acc | (1u64 << i) } else { acc }
+        })
+}
+
+/// Copy a config.toml that failed to load aside, so saving the defaults doesn't lose it
+pub fn keep_rejected_config() -> Result<(), ManagerError> {
+    fs::copy(CONFIG_PATH, REJECTED_CONFIG_PATH)
+        .map(|_| ())
+        .map_err(|e| ManagerError::os(&e, format_args!("Failed to copy {} to {}", CONFIG_PATH, REJECTED_CONFIG_PATH)))
+}
+
+pub fn save_config(config: &Config) -> Result<(), ManagerError> {
+    if let Ok(data) = toml::to_string(config) {
+        fs::write(CONFIG_PATH, data).map_err(|e| ManagerError::os(&e, format_args!("Failed to write {}", CONFIG_PATH)))
+    } else {
+        Err(ManagerError::other("Failed to serialize config"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cgroup_rule(name: &str) -> ProcessConfig {
+        ProcessConfig {
+            name: name.to_string(),
+            cgroup: Some(CgroupLimits::default()),
+            ..Config::default().processes[0].clone()
+        }
+    }
+
+    #[test]
+    fn sets_a_bit_per_selected_core() {
+        assert_eq!(calculate_affinity_mask(&[]), 0);
+        assert_eq!(calculate_affinity_mask(&[false; 8]), 0);
+        assert_eq!(calculate_affinity_mask(&[true, false, true, false]), 0b101);
+        assert_eq!(calculate_affinity_mask(&[false, false, false, true]), 0b1000);
+        assert_eq!(calculate_affinity_mask(&[true; 64]), u64::MAX);
+    }
+
+    #[test]
+    fn rejects_cgroup_subtrees_outside_the_root() {
+        let mut config = Config::default();
+        for subtree in ["", "/", "..", "a/b"] {
+            config.cgroups.subtree = subtree.to_string();
+            assert!(config.validate().is_err(), "{:?}", subtree);
+        }
+    }
+
+    #[test]
+    fn rejects_unusable_watchdog_thresholds() {
+        for threshold in [0.0, -5.0, f64::NAN, f64::INFINITY] {
+            let mut config = Config::default();
+            config.cpu_hog_watchdog.cpu_threshold_percent = threshold;
+            assert!(config.validate().is_err(), "{}", threshold);
+        }
+
+        let mut config = Config::default();
+        config.cpu_hog_watchdog.priority_class = Priority::Realtime;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn protects_the_platforms_system_processes_by_default() {
+        let protected = Config::default().protected_processes;
+        let system = if cfg!(target_os = "linux") { ["systemd", "sshd", "dbus-daemon", "Xorg"] } else { ["csrss.exe", "lsass.exe", "dwm.exe", "svchost.exe"] };
+        for name in system {
+            assert!(ProcessManager::is_protected_process(u32::MAX, name, &protected), "{}", name);
+        }
+    }
+
+    #[test]
+    fn rejects_demotion_that_raises_priority_or_names_missing_cores() {
+        let demotion = |priority_class, core_selections| BackgroundDemotionConfig { enabled: true, allowlist: Vec::new(), priority_class, core_selections };
+
+        assert!(demotion(Priority::Idle, vec![true]).validate().is_ok());
+        assert!(demotion(Priority::Normal, Vec::new()).validate().is_ok());
+        for priority in [Priority::AboveNormal, Priority::High, Priority::Realtime] {
+            assert!(demotion(priority, Vec::new()).validate().is_err(), "{}", priority);
+        }
+
+        let mut beyond_the_mask = vec![false; 65];
+        beyond_the_mask[64] = true;
+        assert!(demotion(Priority::Idle, beyond_the_mask).validate().is_err());
+
+        let cpu_count = ProcessManager::get_system_cpu_count() as usize;
+        if cpu_count < 64 {
+            let mut missing_core = vec![false; cpu_count + 1];
+            missing_core[cpu_count] = true;
+            assert!(demotion(Priority::Idle, missing_core).validate().is_err());
+        }
+
+        let config = Config { background_demotion: demotion(Priority::Realtime, Vec::new()), ..Config::default() };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn rejects_rules_sharing_a_cgroup() {
+        let mut config = Config { processes: vec![cgroup_rule("a b"), cgroup_rule("a_b")], ..Config::default() };
+        assert!(config.validate().unwrap_err().contains("would share cgroup 'a_b'"));
+
+        config.processes = vec![cgroup_rule("..")];
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,231 @@
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::engine::Engine;
+use crate::error::ManagerError;
+use crate::events::EngineEvent;
+use crate::logs::{LogEntry, LogLevel};
+use crate::platform;
+use crate::rpc::{self, Origin, RpcRequest, RpcResponse};
+
+// How often the accept loop checks for an interrupt
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// Turns the connection into a stream of event notifications
+pub const SUBSCRIBE_METHOD: &str = "subscribe";
+const EVENT_METHOD: &str = "event";
+
+/// A JSON-RPC notification carrying an engine event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EventNotification {
+    jsonrpc: String,
+    method: String,
+    params: EngineEvent,
+}
+
+/// Socket the daemon listens on: in the user's runtime directory, or /tmp per user
+pub fn default_socket_path() -> PathBuf {
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(runtime_dir) => Path::new(&runtime_dir).join("pp_manager.sock"),
+        None => PathBuf::from(format!("/tmp/pp_manager-{}.sock", unsafe { libc::getuid() })),
+    }
+}
+
+/// Serve JSON-RPC requests, one per line, until interrupted; each client gets its own thread.
+/// Monitoring is stopped and the socket removed on the way out
+pub fn serve(engine: &Engine, socket_path: &Path) -> Result<(), ManagerError> {
+    if socket_path.exists() {
+        // A socket nobody answers on is left over from a daemon that didn't exit cleanly
+        if UnixStream::connect(socket_path).is_ok() {
+            return Err(ManagerError::other(format!("A daemon is already listening on {}", socket_path.display())));
+        }
+        fs::remove_file(socket_path)
+            .map_err(|e| ManagerError::os(&e, format_args!("Failed to remove stale socket {}", socket_path.display())))?;
+    }
+
+    // The daemon can change and kill processes, so only its owner may connect. The socket
+    // is created owner-only rather than restricted after binding, which would leave a window
+    let previous_umask = unsafe { libc::umask(0o077) };
+    let bound = UnixListener::bind(socket_path);
+    unsafe { libc::umask(previous_umask) };
+    let listener = bound
+        .map_err(|e| ManagerError::os(&e, format_args!("Failed to listen on {}", socket_path.display())))?;
+    fs::set_permissions(socket_path, fs::Permissions::from_mode(0o600))
+        .map_err(|e| ManagerError::os(&e, format_args!("Failed to restrict {}", socket_path.display())))?;
+    listener.set_nonblocking(true).map_err(|e| ManagerError::os(&e, "Failed to set up the socket"))?;
+    log(engine, LogLevel::Info, format!("Daemon listening on {}", socket_path.display()));
+
+    platform::catch_interrupts();
+    while !platform::interrupted() {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let engine = engine.clone();
+                thread::spawn(move || serve_client(&engine, stream));
+            }
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => thread::sleep(ACCEPT_POLL_INTERVAL),
+            Err(err) => {
+                log(engine, LogLevel::Error, format!("Failed to accept a client: {}", err));
+                // Such as running out of file descriptors, which the next accept hits again at once
+                thread::sleep(ACCEPT_POLL_INTERVAL);
+            }
+        }
+    }
+
+    engine.stop();
+    let _ = fs::remove_file(socket_path);
+    Ok(())
+}
+
+fn serve_client(engine: &Engine, stream: UnixStream) {
+    // Accepted sockets inherit non-blocking mode from the listener on some platforms
+    if let Err(err) = stream.set_nonblocking(false) {
+        log(engine, LogLevel::Error, format!("Failed to set up a client connection: {}", err));
+        return;
+    }
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(err) => {
+            log(engine, LogLevel::Error, format!("Failed to set up a client connection: {}", err));
+            return;
+        }
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => {
+                log(engine, LogLevel::Error, format!("Failed to read from a client: {}", err));
+                return;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let subscribing = serde_json::from_str::<RpcRequest>(&line)
+            .is_ok_and(|request| request.method == SUBSCRIBE_METHOD);
+        if subscribing {
+            stream_events(engine, &line, writer);
+            return;
+        }
+
+        let response = rpc::handle_line(engine, Origin::Socket, &line);
+        if writeln!(writer, "{}", response).is_err() {
+            return;
+        }
+    }
+}
+
+/// Log to the engine, where daemon clients see it; nobody may be watching the daemon's stderr
+fn log(engine: &Engine, level: LogLevel, message: String) {
+    engine.logs.lock().unwrap().push(LogEntry::new(level, message));
+}
+
+/// Acknowledge a subscription, then forward events until the client goes away
+fn stream_events(engine: &Engine, request_line: &str, mut writer: UnixStream) {
+    let Ok(request) = serde_json::from_str::<RpcRequest>(request_line) else {
+        return;
+    };
+    let events = engine.subscribe();
+
+    let ack = RpcResponse::new(request.id, Ok(json!(true)));
+    if writeln!(writer, "{}", serde_json::to_string(&ack).unwrap_or_default()).is_err() {
+        return;
+    }
+
+    for event in events {
+        let notification = EventNotification {
+            jsonrpc: "2.0".to_string(),
+            method: EVENT_METHOD.to_string(),
+            params: event,
+        };
+        let line = serde_json::to_string(&notification).unwrap_or_default();
+        if writeln!(writer, "{}", line).is_err() {
+            return;
+        }
+    }
+}
+
+/// A connection to a running daemon
+pub struct Client {
+    socket_path: PathBuf,
+    next_id: AtomicU64,
+}
+
+impl Client {
+    /// Connect to the daemon, failing if none is listening
+    pub fn connect(socket_path: &Path) -> Result<Self, ManagerError> {
+        UnixStream::connect(socket_path)
+            .map_err(|e| ManagerError::backend_unavailable(format!("No daemon listening on {}: {}", socket_path.display(), e)))?;
+
+        Ok(Self {
+            socket_path: socket_path.to_path_buf(),
+            next_id: AtomicU64::new(1),
+        })
+    }
+
+    /// Call a method; each call uses its own connection, so a restarted daemon is picked up
+    pub fn call(&self, method: &str, params: Value) -> Result<Value, ManagerError> {
+        let stream = UnixStream::connect(&self.socket_path)
+            .map_err(|e| ManagerError::backend_unavailable(format!("Lost connection to the daemon: {}", e)))?;
+        let mut writer = stream.try_clone().map_err(|e| ManagerError::os(&e, "Failed to use the daemon connection"))?;
+
+        let request = RpcRequest::new(self.next_id.fetch_add(1, Ordering::Relaxed), method, params);
+        let line = serde_json::to_string(&request).map_err(|e| ManagerError::other(e.to_string()))?;
+        writeln!(writer, "{}", line).map_err(|e| ManagerError::backend_unavailable(format!("Failed to send request: {}", e)))?;
+
+        let mut response = String::new();
+        BufReader::new(stream)
+            .read_line(&mut response)
+            .map_err(|e| ManagerError::backend_unavailable(format!("Failed to read response: {}", e)))?;
+
+        let response: RpcResponse = serde_json::from_str(&response)
+            .map_err(|e| ManagerError::backend_unavailable(format!("Invalid response: {}", e)))?;
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(error.into()),
+            (Some(result), None) => Ok(result),
+            (None, None) => Ok(Value::Null),
+        }
+    }
+
+    /// Receive the daemon's events on a connection of their own; the receiver ends when the daemon exits
+    pub fn subscribe(&self) -> Result<Receiver<EngineEvent>, ManagerError> {
+        let stream = UnixStream::connect(&self.socket_path)
+            .map_err(|e| ManagerError::backend_unavailable(format!("Lost connection to the daemon: {}", e)))?;
+        let mut writer = stream.try_clone().map_err(|e| ManagerError::os(&e, "Failed to use the daemon connection"))?;
+
+        let request = RpcRequest::new(self.next_id.fetch_add(1, Ordering::Relaxed), SUBSCRIBE_METHOD, json!({}));
+        let line = serde_json::to_string(&request).map_err(|e| ManagerError::other(e.to_string()))?;
+        writeln!(writer, "{}", line).map_err(|e| ManagerError::backend_unavailable(format!("Failed to send request: {}", e)))?;
+
+        let mut lines = BufReader::new(stream).lines();
+        match lines.next() {
+            Some(Ok(ack)) if serde_json::from_str::<RpcResponse>(&ack).is_ok_and(|response| response.error.is_none()) => {}
+            _ => return Err(ManagerError::backend_unavailable("The daemon refused the subscription")),
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in lines.map_while(Result::ok) {
+                let Ok(notification) = serde_json::from_str::<EventNotification>(&line) else {
+                    continue;
+                };
+                if sender.send(notification.params).is_err() {
+                    return;
+                }
+            }
+        });
+
+        Ok(receiver)
+    }
+}
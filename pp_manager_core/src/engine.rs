@@ -0,0 +1,702 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use chrono::{DateTime, Local};
+use serde_json::{json, Value};
+
+use crate::cgroup::CgroupTree;
+use crate::config::{self, calculate_affinity_mask, BackgroundDemotionConfig, Config, CpuHogWatchdogConfig, ProcessConfig, RealtimeGuardConfig};
+use crate::error::ManagerError;
+use crate::events::{EngineEvent, EventBus};
+use crate::log_files::{self, ExportFormat, LogExport, LogFiles};
+use crate::log_throttle::LogThrottle;
+use crate::logs::{LogEntry, LogLevel, LogQuery, LogStore};
+use crate::priority::Priority;
+use crate::supervisor::Supervisor;
+use crate::process_manager::{
+    format_pid_list, CpuHogPolicy, InstanceLimitAction, ProcessManager, ProcessRule, ProcessState, RealtimeGuardPolicy,
+    ReservationPreview, TerminateOptions, TerminationResult,
+};
+
+// Time between monitoring passes
+pub const MONITOR_INTERVAL: Duration = Duration::from_secs(2);
+
+// How long a kill waits for a close request to work before forcing termination
+pub const DEFAULT_GRACEFUL_TIMEOUT_SECONDS: u64 = 5;
+
+/// The manager's shared state and monitoring loop, independent of any user interface
+#[derive(Clone)]
+pub struct Engine {
+    pub process_manager: Arc<Mutex<ProcessManager>>,
+    pub monitor: Arc<Mutex<Supervisor>>,
+    pub logs: Arc<Mutex<LogStore>>,
+    pub config: Arc<Mutex<Config>>,
+    pub events: EventBus,
+}
+
+impl Engine {
+    /// Load config.toml; a rejected config falls back to defaults, but the reason is kept in the log
+    /// and the file itself in config.toml.bak, since the next save overwrites it
+    pub fn new() -> Self {
+        let (config, logs) = match config::load_config() {
+            Ok(config) => (config, Vec::new()),
+            Err(err) => {
+                let kept = match config::keep_rejected_config() {
+                    Ok(()) => LogEntry::new(LogLevel::Info, format!(
+                        "Using default settings; the rejected config was copied to {}", config::REJECTED_CONFIG_PATH
+                    )),
+                    Err(copy_err) => LogEntry::new(LogLevel::Error, format!(
+                        "{}; saving settings will overwrite the rejected config", copy_err
+                    )),
+                };
+                (Config::default(), vec![LogEntry::new(LogLevel::Error, err.to_string()), kept])
+            }
+        };
+
+        Self::with_config(config, logs)
+    }
+
+    /// An engine over a config that is already loaded
+    pub fn with_config(config: Config, logs: Vec<LogEntry>) -> Self {
+        let mut log_store = LogStore::new(config.log_capacity);
+        let mut logs = logs;
+        if config.log_files.enabled {
+            match LogFiles::open(&config.log_files) {
+                Ok(files) => log_store.set_files(Some(files)),
+                Err(err) => logs.push(LogEntry::new(LogLevel::Error, err.to_string())),
+            }
+        }
+        log_store.extend(logs);
+
+        let mut process_manager = ProcessManager::new();
+        process_manager.set_kill_audit_directory(&log_files::log_directory(&config.log_files));
+
+        Self {
+            process_manager: Arc::new(Mutex::new(process_manager)),
+            monitor: Arc::new(Mutex::new(Supervisor::default())),
+            logs: Arc::new(Mutex::new(log_store)),
+            config: Arc::new(Mutex::new(config)),
+            events: EventBus::default(),
+        }
+    }
+
+    /// Receive events from now on; dropping the receiver unsubscribes
+    pub fn subscribe(&self) -> Receiver<EngineEvent> {
+        self.events.subscribe()
+    }
+
+    /// Log entries after the one numbered `seq`; 0 returns the whole log
+    pub fn logs_since(&self, seq: u64) -> Vec<LogEntry> {
+        self.logs.lock().unwrap().since(seq)
+    }
+
+    pub fn query_logs(&self, query: &LogQuery) -> Vec<LogEntry> {
+        self.logs.lock().unwrap().query(query)
+    }
+
+    /// Write the entries of a time range to a CSV or JSON file, by default in the log directory,
+    /// where a relative path is also placed. The log files are the source when enabled, otherwise
+    /// the entries still in memory
+    pub fn export_logs(
+        &self,
+        since: Option<DateTime<Local>>,
+        until: Option<DateTime<Local>>,
+        format: ExportFormat,
+        path: Option<PathBuf>,
+    ) -> Result<LogExport, ManagerError> {
+        let directory = self.logs.lock().unwrap().files().map(|files| files.directory().to_path_buf());
+        let entries = match &directory {
+            Some(directory) => log_files::read_range(directory, since, until)?,
+            None => self.query_logs(&LogQuery { since, until, ..LogQuery::default() }),
+        };
+
+        let path = match path {
+            Some(path) if path.is_absolute() => path,
+            path => {
+                let directory = directory.unwrap_or_else(|| log_files::log_directory(&self.config().log_files));
+                fs::create_dir_all(&directory).map_err(|e| ManagerError::os(&e, format_args!("Failed to create {}", directory.display())))?;
+                let extension = match format {
+                    ExportFormat::Csv => "csv",
+                    ExportFormat::Json => "json",
+                };
+                directory.join(path.unwrap_or_else(|| format!("export-{}.{}", Local::now().format("%Y%m%d-%H%M%S"), extension).into()))
+            }
+        };
+
+        log_files::export(&entries, format, &path)?;
+        Ok(LogExport { path: path.display().to_string(), entries: entries.len() })
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.monitor.lock().unwrap().is_running()
+    }
+
+    /// Start the monitoring loop on its own thread
+    pub fn start(&self) -> Result<(), ManagerError> {
+        // Held until the loop is spawned, so concurrent starts and stops take turns
+        let mut monitor = self.monitor.lock().unwrap();
+        if monitor.is_running() {
+            return Err(ManagerError::other("Monitoring is already running"));
+        }
+
+        let process_manager = Arc::clone(&self.process_manager);
+        let logs_clone = Arc::clone(&self.logs);
+        let config_clone = Arc::clone(&self.config);
+        let events = self.events.clone();
+        let config = self.config.lock().unwrap().clone();
+        let throttle_config = config.log_throttle.clone();
+
+        // Check if we should use multi-process mode or legacy single process mode
+        let use_multi_process = !config.processes.is_empty() &&
+                               config.processes.iter().any(|p| p.enabled);
+
+        if use_multi_process {
+            // Multi-process mode
+            let process_rules = match config.enabled_rules() {
+                Ok(rules) => rules,
+                Err(err) => return Err(ManagerError::invalid_config(err)),
+            };
+
+            let has_realtime_rules = process_rules.iter().any(|rule| rule.is_realtime());
+            let reserved_mask = config::reserved_core_mask(&config);
+            let reservation_exempt = config::enabled_process_names(&config);
+            let protected_processes = config.protected_processes.clone();
+
+            let available_mask = ProcessManager::get_system_affinity_mask();
+            if reserved_mask != 0 && available_mask & !reserved_mask == 0 {
+                return Err(ManagerError::invalid_config(format!("Core reservation 0x{:X} leaves no cores for other processes", reserved_mask)));
+            }
+
+            if process_rules.iter().any(|rule| rule.cgroup.is_some()) {
+                let tree = CgroupTree::new(Path::new(&config.cgroups.root), &config.cgroups.subtree)?;
+                match process_manager.lock().unwrap().enable_cgroups(tree) {
+                    Ok(cgroup_logs) => logs_clone.lock().unwrap().extend(cgroup_logs),
+                    Err(err) => return Err(err),
+                }
+            }
+
+            let on_panic = self.panic_reporter();
+            monitor.spawn(move |token| {
+                events.emit(EngineEvent::MonitoringStarted);
+                let mut last_state = ProcessState::NotFound;
+                let mut throttle = LogThrottle::new(throttle_config);
+
+                while !token.is_cancelled() {
+                    let (current_state, pass_events, pass_logs) = {
+                        let mut pm = process_manager.lock().unwrap();
+                        let state = pm.check_and_manage_multiple_processes(&process_rules);
+                        (state, pm.take_events(), pm.take_logs())
+                    };
+                    logs_clone.lock().unwrap().extend(throttle.admit(pass_logs));
+                    events.emit_all(pass_events.into_iter().filter(|event| throttle.admit_event(event)).collect());
+
+                    let suspend_logs = process_manager.lock().unwrap()
+                        .enforce_suspend_rules(&process_rules, &protected_processes);
+                    logs_clone.lock().unwrap().extend(throttle.admit(suspend_logs));
+
+                    let instance_logs = process_manager.lock().unwrap()
+                        .enforce_instance_limits(&process_rules, &protected_processes);
+                    logs_clone.lock().unwrap().extend(throttle.admit(instance_logs));
+
+                    // The real-time guard always runs; only its limits are configurable, and read live
+                    if has_realtime_rules {
+                        let guard = config_clone.lock().unwrap().realtime_guard.clone();
+                        let policy = RealtimeGuardPolicy {
+                            cpu_threshold_percent: guard.cpu_threshold_percent,
+                            max_full_core: Duration::from_secs(guard.max_full_core_seconds),
+                            calm: Duration::from_secs(guard.calm_seconds),
+                            priority: guard.priority_class,
+                        };
+                        let guard_logs = process_manager.lock().unwrap()
+                            .guard_realtime_processes(&process_rules, &policy);
+                        logs_clone.lock().unwrap().extend(throttle.admit(guard_logs));
+                    }
+
+                    if reserved_mask != 0 {
+                        let (reservation_logs, reservation_events) = {
+                            let mut pm = process_manager.lock().unwrap();
+                            let logs = pm.enforce_core_reservation(reserved_mask, &reservation_exempt, &protected_processes);
+                            (logs, pm.take_events())
+                        };
+                        logs_clone.lock().unwrap().extend(throttle.admit(reservation_logs));
+                        events.emit_all(reservation_events.into_iter().filter(|event| throttle.admit_event(event)).collect());
+                    }
+
+                    // Background demotion is read live so it can be switched off while monitoring
+                    let demotion = config_clone.lock().unwrap().background_demotion.clone();
+                    let demotion_logs = {
+                        let mut pm = process_manager.lock().unwrap();
+                        if demotion.enabled {
+                            let allowlist: Vec<String> = demotion.allowlist.iter()
+                                .chain(reservation_exempt.iter())
+                                .cloned()
+                                .collect();
+                            // Kept off the reserved cores, or the reservation and demotion would undo each other every pass
+                            let affinity_mask = Some(calculate_affinity_mask(&demotion.core_selections))
+                                .filter(|&mask| mask != 0)
+                                .map(|mask| ProcessManager::restrict_to_unreserved_cores(mask, reserved_mask, available_mask));
+                            pm.enforce_background_demotion(&allowlist, &protected_processes, demotion.priority_class, affinity_mask)
+                        } else {
+                            pm.restore_demoted_processes()
+                        }
+                    };
+                    logs_clone.lock().unwrap().extend(throttle.admit(demotion_logs));
+
+                    let watchdog = config_clone.lock().unwrap().cpu_hog_watchdog.clone();
+                    let watchdog_logs = {
+                        let mut pm = process_manager.lock().unwrap();
+                        if watchdog.enabled {
+                            let policy = CpuHogPolicy {
+                                cpu_threshold_percent: watchdog.cpu_threshold_percent,
+                                sustained: Duration::from_secs(watchdog.sustained_seconds),
+                                calm: Duration::from_secs(watchdog.calm_seconds),
+                                priority: watchdog.priority_class,
+                                affinity_mask: Some(calculate_affinity_mask(&watchdog.core_selections))
+                                    .filter(|&mask| mask != 0)
+                                    .map(|mask| ProcessManager::restrict_to_unreserved_cores(mask, reserved_mask, available_mask)),
+                            };
+                            let exemptions: Vec<String> = watchdog.exemptions.iter()
+                                .chain(reservation_exempt.iter())
+                                .cloned()
+                                .collect();
+                            pm.watch_cpu_hogs(&policy, &exemptions, &protected_processes)
+                        } else {
+                            pm.restore_cpu_hogs()
+                        }
+                    };
+                    logs_clone.lock().unwrap().extend(throttle.admit(watchdog_logs));
+
+                    // Reapplied settings and errors are logged per process, with their details
+                    let should_log = current_state != last_state
+                        && !matches!(current_state, ProcessState::MultiProcessSettingsApplied(_) | ProcessState::Error(_));
+
+                    if should_log {
+                        let message = match &current_state {
+                            ProcessState::NotFound => "No configured processes running.".to_string(),
+                            ProcessState::MultiProcessFound(process_map) => {
+                                let details: Vec<String> = process_map.iter()
+                                    .map(|(name, pids)| format!("{}: {} instances", name, pids.len()))
+                                    .collect();
+                                format!("Processes found: {}", details.join(", "))
+                            },
+                            ProcessState::MultiProcessMonitoring(process_map) => {
+                                let total_pids: usize = process_map.values().map(|pids| pids.len()).sum();
+                                format!("Monitoring {} processes with {} total instances.", process_map.len(), total_pids)
+                            },
+                            ProcessState::Error(err) => format!("Error: {}", err),
+                            _ => "Unexpected state in multi-process mode".to_string(),
+                        };
+                        let level = match &current_state {
+                            ProcessState::Error(_) => LogLevel::Error,
+                            ProcessState::MultiProcessFound(_) => LogLevel::Success,
+                            ProcessState::MultiProcessMonitoring(_) => LogLevel::Monitor,
+                            ProcessState::NotFound => LogLevel::Info,
+                            _ => LogLevel::Info,
+                        };
+
+                        let entry = LogEntry::new(level, message);
+                        logs_clone.lock().unwrap().extend(throttle.admit(vec![entry]));
+                    }
+
+                    // Repeats that stopped, or keep going, are reported with their count
+                    logs_clone.lock().unwrap().extend(throttle.flush(Local::now()));
+
+                    last_state = current_state;
+                    if token.wait(MONITOR_INTERVAL) {
+                        break;
+                    }
+                }
+
+                // Monitoring stopped: suspensions, the reservation, demotion, watchdogs and cgroups end with it.
+                // Each feature recorded the settings the ones applied before it left, so they are undone
+                // in reverse: the CPU hog watchdog, then demotion, then the reservation
+                let mut pm = process_manager.lock().unwrap();
+                let mut release_logs = throttle.finish();
+                release_logs.extend(pm.resume_all_suspended());
+                release_logs.extend(pm.restore_cpu_hogs());
+                release_logs.extend(pm.restore_demoted_processes());
+                release_logs.extend(pm.release_core_reservation());
+                release_logs.extend(pm.release_realtime_guard());
+                release_logs.extend(pm.release_instance_limits());
+                release_logs.extend(pm.release_cgroups());
+                logs_clone.lock().unwrap().extend(release_logs);
+                events.emit(EngineEvent::MonitoringStopped);
+            }, on_panic);
+        } else {
+            // Legacy single process mode has no way to acknowledge real-time settings
+            if config.priority_class == Priority::Realtime {
+                return Err(ManagerError::invalid_config("Realtime priority needs a process rule with realtime_acknowledged = true"));
+            }
+
+            // Legacy single process mode, whose one rule goes through the same instance limits as process rules
+            let target_process = config.target_process.clone();
+            let rule = ProcessRule::new(&target_process, calculate_affinity_mask(&config.core_selections), config.priority_class);
+            let protected_processes = config.protected_processes.clone();
+
+            let on_panic = self.panic_reporter();
+            monitor.spawn(move |token| {
+                events.emit(EngineEvent::MonitoringStarted);
+                let mut last_state = ProcessState::NotFound;
+                let mut throttle = LogThrottle::new(throttle_config);
+
+                while !token.is_cancelled() {
+                    let (current_state, pass_events) = {
+                        let mut pm = process_manager.lock().unwrap();
+                        let state = pm.check_and_manage_process(&rule);
+                        (state, pm.take_events())
+                    };
+                    events.emit_all(pass_events.into_iter().filter(|event| throttle.admit_event(event)).collect());
+
+                    let instance_logs = process_manager.lock().unwrap()
+                        .enforce_instance_limits(std::slice::from_ref(&rule), &protected_processes);
+                    logs_clone.lock().unwrap().extend(throttle.admit(instance_logs));
+
+                    // Log based on state changes and important events
+                    let should_log = match (&current_state, &last_state) {
+                        // Always log state changes
+                        (ProcessState::NotFound, ProcessState::Found(_)) |
+                        (ProcessState::NotFound, ProcessState::FoundAndMonitoring(_)) |
+                        (ProcessState::NotFound, ProcessState::SettingsApplied(_, _)) |
+                        (ProcessState::NotFound, ProcessState::MultipleFound(_)) |
+                        (ProcessState::NotFound, ProcessState::MultipleMonitoring(_)) |
+                        (ProcessState::NotFound, ProcessState::MultipleSettingsApplied(_)) => true,
+                        (ProcessState::Found(_), ProcessState::NotFound) |
+                        (ProcessState::FoundAndMonitoring(_), ProcessState::NotFound) |
+                        (ProcessState::SettingsApplied(_, _), ProcessState::NotFound) |
+                        (ProcessState::MultipleFound(_), ProcessState::NotFound) |
+                        (ProcessState::MultipleMonitoring(_), ProcessState::NotFound) |
+                        (ProcessState::MultipleSettingsApplied(_), ProcessState::NotFound) => true,
+                        // Log when process is first found
+                        (ProcessState::Found(_), _) if last_state == ProcessState::NotFound => true,
+                        (ProcessState::MultipleFound(_), _) if last_state == ProcessState::NotFound => true,
+                        // Log when settings are reapplied
+                        (ProcessState::SettingsApplied(_, _), _) => true,
+                        (ProcessState::MultipleSettingsApplied(_), _) => true,
+                        // Log errors
+                        (ProcessState::Error(_), _) => true,
+                        // Don't spam logs for continuous monitoring
+                        _ => false,
+                    };
+
+                    if should_log {
+                        let message = match &current_state {
+                            ProcessState::NotFound => format!("{} not running.", target_process),
+                            ProcessState::Found(pid) => format!("{} found (PID: {}). Initial settings applied.", target_process, pid),
+                            ProcessState::FoundAndMonitoring(pid) => format!("{} (PID: {}) monitoring active.", target_process, pid),
+                            ProcessState::SettingsApplied(pid, changes) => format!("{} (PID: {}) settings reapplied: {}", target_process, pid, changes),
+                            ProcessState::MultipleFound(pids) => format!("{} {} instances found (PIDs: {}). Initial settings applied.", target_process, pids.len(), format_pid_list(pids)),
+                            ProcessState::MultipleMonitoring(pids) => format!("{} {} instances (PIDs: {}) monitoring active.", target_process, pids.len(), format_pid_list(pids)),
+                            ProcessState::MultipleSettingsApplied(pid_changes) => {
+                                let changes_str = pid_changes.iter()
+                                    .map(|(pid, changes)| format!("PID {}: {}", pid, changes))
+                                    .collect::<Vec<_>>()
+                                    .join("; ");
+                                format!("{} {} instances settings reapplied: {}", target_process, pid_changes.len(), changes_str)
+                            },
+                            ProcessState::MultiProcessFound(process_map) => {
+                                let total_pids: usize = process_map.values().map(|pids| pids.len()).sum();
+                                format!("Multi-process monitoring: {} processes, {} total instances found.", process_map.len(), total_pids)
+                            },
+                            ProcessState::MultiProcessMonitoring(process_map) => {
+                                let total_pids: usize = process_map.values().map(|pids| pids.len()).sum();
+                                format!("Multi-process monitoring: {} processes, {} total instances active.", process_map.len(), total_pids)
+                            },
+                            ProcessState::MultiProcessSettingsApplied(process_map) => {
+                                let total_changes: usize = process_map.values().map(|changes| changes.len()).sum();
+                                format!("Multi-process monitoring: {} processes, {} settings reapplied.", process_map.len(), total_changes)
+                            },
+                            ProcessState::Error(err) => format!("Error: {}", err),
+                        };
+                        let level = match &current_state {
+                            ProcessState::Error(_) => LogLevel::Error,
+                            ProcessState::Found(_) | ProcessState::MultipleFound(_) | ProcessState::MultiProcessFound(_) => LogLevel::Success,
+                            ProcessState::SettingsApplied(_, _) | ProcessState::MultipleSettingsApplied(_) | ProcessState::MultiProcessSettingsApplied(_) => LogLevel::Reapply,
+                            ProcessState::FoundAndMonitoring(_) | ProcessState::MultipleMonitoring(_) | ProcessState::MultiProcessMonitoring(_) => LogLevel::Monitor,
+                            ProcessState::NotFound => LogLevel::Info,
+                        };
+
+                        let entry = LogEntry::new(level, message);
+                        logs_clone.lock().unwrap().extend(throttle.admit(vec![entry]));
+                    }
+
+                    logs_clone.lock().unwrap().extend(throttle.flush(Local::now()));
+
+                    last_state = current_state;
+                    if token.wait(MONITOR_INTERVAL) {
+                        break;
+                    }
+                }
+
+                let mut release_logs = throttle.finish();
+                release_logs.extend(process_manager.lock().unwrap().release_instance_limits());
+                logs_clone.lock().unwrap().extend(release_logs);
+                events.emit(EngineEvent::MonitoringStopped);
+            }, on_panic);
+        }
+
+        Ok(())
+    }
+
+    /// Log a panicked monitoring loop and tell clients it has stopped, since nothing else would;
+    /// the log may be poisoned by the panic, but it is still usable
+    fn panic_reporter(&self) -> impl FnOnce(String) + Send + 'static {
+        let logs = Arc::clone(&self.logs);
+        let events = self.events.clone();
+        move |panic| {
+            let message = format!("The monitoring loop stopped after a panic: {}; start monitoring to restart it", panic);
+            logs.lock().unwrap_or_else(PoisonError::into_inner).push(LogEntry::new(LogLevel::Error, message.clone()));
+            events.emit(EngineEvent::Error { message });
+            events.emit(EngineEvent::MonitoringStopped);
+        }
+    }
+
+    /// Stop the monitoring loop and wait for it to restore what it changed; manual suspensions
+    /// don't outlive monitoring either. The log files are flushed once everything is logged
+    pub fn stop(&self) {
+        self.monitor.lock().unwrap().stop();
+
+        let resume_logs = self.process_manager.lock().unwrap().resume_all_suspended();
+        let mut logs = self.logs.lock().unwrap();
+        logs.extend(resume_logs);
+        logs.flush();
+    }
+
+    pub fn config(&self) -> Config {
+        self.config.lock().unwrap().clone()
+    }
+
+    pub fn save_config(&self, config: Config) -> Result<(), ManagerError> {
+        config.validate().map_err(ManagerError::invalid_config)?;
+
+        let log_files_changed = self.config.lock().unwrap().log_files != config.log_files;
+        {
+            let mut logs = self.logs.lock().unwrap();
+            logs.set_capacity(config.log_capacity);
+            if log_files_changed {
+                let files = config.log_files.enabled
+                    .then(|| LogFiles::open(&config.log_files))
+                    .transpose()?;
+                logs.set_files(files);
+                self.process_manager.lock().unwrap().set_kill_audit_directory(&log_files::log_directory(&config.log_files));
+            }
+        }
+
+        *self.config.lock().unwrap() = config.clone();
+        config::save_config(&config)
+    }
+
+    pub fn add_process_config(&self, name: String, core_selections: Vec<bool>, priority_class: Priority) -> Result<(), ManagerError> {
+        let mut config = self.config.lock().unwrap();
+
+        // Check if process already exists
+        if config.processes.iter().any(|p| p.name == name) {
+            return Err(ManagerError::invalid_config(format!("Process '{}' already exists", name)));
+        }
+
+        let process_config = ProcessConfig {
+            name,
+            core_selections,
+            priority_class,
+            enabled: true,
+            exclusive_cores: false,
+            scheduling_policy: None,
+            rt_priority: None,
+            realtime_acknowledged: false,
+            io_priority: None,
+            cgroup: None,
+            oom_score_adj: None,
+            numa: None,
+            thread_rules: Vec::new(),
+            suspend_while_running: Vec::new(),
+            max_instances: None,
+            extra_instance_action: InstanceLimitAction::Warn,
+        };
+
+        // Realtime can only be chosen for a rule that has acknowledged the risk
+        process_config.to_rule().map_err(ManagerError::invalid_config)?;
+        config.processes.push(process_config);
+
+        config::save_config(&config)
+    }
+
+    pub fn remove_process_config(&self, name: &str) -> Result<(), ManagerError> {
+        let mut config = self.config.lock().unwrap();
+
+        let initial_len = config.processes.len();
+        config.processes.retain(|p| p.name != name);
+
+        if config.processes.len() == initial_len {
+            return Err(ManagerError::invalid_config(format!("Process '{}' not found", name)));
+        }
+
+        config::save_config(&config)
+    }
+
+    pub fn update_process_config(&self, name: &str, core_selections: Vec<bool>, priority_class: Priority, enabled: bool, exclusive_cores: Option<bool>) -> Result<(), ManagerError> {
+        let mut config = self.config.lock().unwrap();
+
+        let Some(process_config) = config.processes.iter_mut().find(|p| p.name == name) else {
+            return Err(ManagerError::invalid_config(format!("Process '{}' not found", name)));
+        };
+
+        let mut updated = process_config.clone();
+        updated.core_selections = core_selections;
+        updated.priority_class = priority_class;
+        updated.enabled = enabled;
+        if let Some(exclusive_cores) = exclusive_cores {
+            updated.exclusive_cores = exclusive_cores;
+        }
+
+        // Realtime can only be chosen for a rule that has acknowledged the risk
+        updated.to_rule().map_err(ManagerError::invalid_config)?;
+        *process_config = updated;
+
+        config::save_config(&config)
+    }
+
+    /// Replace every setting of an existing rule, including the advanced ones
+    /// that update_process_config leaves alone
+    pub fn update_process_rule(&self, process_config: ProcessConfig) -> Result<(), ManagerError> {
+        let mut config = self.config.lock().unwrap();
+
+        let mut updated = config.clone();
+        let Some(existing) = updated.processes.iter_mut().find(|p| p.name == process_config.name) else {
+            return Err(ManagerError::invalid_config(format!("Process '{}' not found", process_config.name)));
+        };
+        *existing = process_config;
+
+        // Checked against the other rules too, whose cgroups it mustn't share
+        updated.validate().map_err(ManagerError::invalid_config)?;
+        *config = updated;
+
+        config::save_config(&config)
+    }
+
+    pub fn update_background_demotion(&self, demotion: BackgroundDemotionConfig) -> Result<(), ManagerError> {
+        demotion.validate().map_err(ManagerError::invalid_config)?;
+
+        let mut config = self.config.lock().unwrap();
+        config.background_demotion = demotion;
+        config::save_config(&config)
+    }
+
+    pub fn update_cpu_hog_watchdog(&self, watchdog: CpuHogWatchdogConfig) -> Result<(), ManagerError> {
+        watchdog.validate().map_err(ManagerError::invalid_config)?;
+
+        let mut config = self.config.lock().unwrap();
+        config.cpu_hog_watchdog = watchdog;
+        config::save_config(&config)
+    }
+
+    pub fn update_realtime_guard(&self, guard: RealtimeGuardConfig) -> Result<(), ManagerError> {
+        guard.validate().map_err(ManagerError::invalid_config)?;
+
+        let mut config = self.config.lock().unwrap();
+        config.realtime_guard = guard;
+        config::save_config(&config)
+    }
+
+    /// Terminate a process on behalf of the user, logging the outcome
+    pub fn kill_process(&self, pid: u32, options: TerminateOptions) -> Result<TerminationResult, ManagerError> {
+        let protected_processes = self.config.lock().unwrap().protected_processes.clone();
+        let started = self.process_manager.lock().unwrap().start_kill(pid, options, "user action", &protected_processes);
+
+        // Waiting can take the whole graceful timeout; the monitor and other requests carry on meanwhile
+        let finished = started.and_then(|mut termination| {
+            termination.wait();
+            self.process_manager.lock().unwrap().finish_kill(termination)
+        });
+
+        // A kill audit log that couldn't be written is reported here, not at the next monitoring pass
+        let audit_logs = self.process_manager.lock().unwrap().take_logs();
+        self.logs.lock().unwrap().extend(audit_logs);
+        let result = finished?;
+
+        let mut message = format!("Terminated PID {}: exited {}", pid, format_pid_list(&result.exited));
+        if !result.survived.is_empty() {
+            message.push_str(&format!(", survived {}", format_pid_list(&result.survived)));
+        }
+        let level = if result.survived.is_empty() { LogLevel::Info } else { LogLevel::Error };
+        self.logs.lock().unwrap().push(LogEntry::new(level, message));
+
+        Ok(result)
+    }
+
+    pub fn suspend_process(&self, pid: u32, include_tree: bool) -> Result<Vec<u32>, ManagerError> {
+        let protected_processes = self.config.lock().unwrap().protected_processes.clone();
+        let suspended = self.process_manager.lock().unwrap()
+            .suspend_process(pid, include_tree, "manual", &protected_processes)?;
+
+        self.logs.lock().unwrap().push(LogEntry::new(LogLevel::Suspend, format!("Suspended PIDs: {}", format_pid_list(&suspended))));
+        Ok(suspended)
+    }
+
+    pub fn resume_process(&self, pid: u32) -> Result<Vec<u32>, ManagerError> {
+        let resumed = self.process_manager.lock().unwrap().resume_process(pid)?;
+
+        self.logs.lock().unwrap().push(LogEntry::new(LogLevel::Suspend, format!("Resumed PIDs: {}", format_pid_list(&resumed))));
+        Ok(resumed)
+    }
+
+    /// Tracked processes with the settings last applied to them
+    pub fn tracked_processes(&self) -> Vec<Value> {
+        let process_manager = self.process_manager.lock().unwrap();
+        process_manager.get_tracked_processes()
+            .values()
+            .map(|instance| json!({
+                "pid": instance.pid,
+                "last_applied_affinity": instance.last_applied_affinity,
+                "last_applied_priority": instance.last_applied_priority,
+                "failures": instance.failures,
+                "unmanageable": instance.unmanageable
+            }))
+            .collect()
+    }
+
+    /// Current and last applied settings of one process, flattened for display
+    pub fn process_details(&self, pid: u32) -> Result<Value, ManagerError> {
+        let process_manager = self.process_manager.lock().unwrap();
+        let details = process_manager.get_process_details(pid)?;
+        Ok(json!({
+            "pid": details.pid,
+            "name": details.name,
+            "current_priority": details.current_priority,
+            "current_affinity": details.current_affinity,
+            "current_scheduling_policy": details.current_scheduling.map(|s| s.policy),
+            "current_rt_priority": details.current_scheduling.map(|s| s.rt_priority),
+            "last_applied_priority": details.last_applied_priority,
+            "last_applied_affinity": details.last_applied_affinity,
+            "last_applied_scheduling_policy": details.last_applied_scheduling.map(|s| s.policy),
+            "current_io_priority": details.current_io_priority,
+            "last_applied_io_priority": details.last_applied_io_priority,
+            "cgroup": details.cgroup,
+            "memory_high": details.memory_limits.as_ref().and_then(|m| m.memory_high.clone()),
+            "memory_max": details.memory_limits.as_ref().and_then(|m| m.memory_max.clone()),
+            "current_oom_score_adj": details.current_oom_score_adj,
+            "last_applied_oom_score_adj": details.last_applied_oom_score_adj,
+            "numa_policies": details.numa_usage.as_ref().map(|usage| usage.policies.clone()),
+            "numa_pages_per_node": details.numa_usage.as_ref().map(|usage| usage.pages_per_node.clone()),
+            "last_applied_numa": details.last_applied_numa.map(|numa| numa.to_string()),
+            "is_tracked": details.is_tracked
+        }))
+    }
+
+    pub fn preview_core_reservation(&self) -> Result<Vec<ReservationPreview>, ManagerError> {
+        let config = self.config();
+        let reserved_mask = config::reserved_core_mask(&config);
+        if reserved_mask == 0 {
+            return Ok(Vec::new());
+        }
+
+        let process_manager = self.process_manager.lock().unwrap();
+        process_manager.preview_core_reservation(reserved_mask, &config::enabled_process_names(&config), &config.protected_processes)
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,149 @@
+use std::fmt;
+use std::io;
+use serde::{Deserialize, Serialize};
+
+/// Why an operation failed, so callers can tell a process that simply went away from
+/// one that needs elevation. Serialised with a `kind` tag and the message to show, e.g.
+/// `{"kind":"access_denied","pid":42,"message":"Access denied to ..."}`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ManagerError {
+    /// The OS refused; running elevated (root, CAP_SYS_NICE, Administrator) would help
+    AccessDenied { pid: Option<u32>, message: String },
+    /// The process exited between being found and being opened or changed
+    ProcessExited { pid: u32, message: String },
+    /// A system or configured protected process, which is never touched
+    ProtectedProcess { pid: u32, message: String },
+    /// A rule or setting that can't be applied as written
+    InvalidConfig { message: String },
+    /// The daemon isn't reachable, or this platform can't do what was asked
+    BackendUnavailable { message: String },
+    /// Any other failure, with the OS error code when there is one
+    Os { code: Option<i32>, message: String },
+}
+
+impl ManagerError {
+    pub fn access_denied(pid: impl Into<Option<u32>>, message: impl Into<String>) -> Self {
+        ManagerError::AccessDenied { pid: pid.into(), message: message.into() }
+    }
+
+    pub fn process_exited(pid: u32, message: impl Into<String>) -> Self {
+        ManagerError::ProcessExited { pid, message: message.into() }
+    }
+
+    pub fn protected_process(pid: u32, message: impl Into<String>) -> Self {
+        ManagerError::ProtectedProcess { pid, message: message.into() }
+    }
+
+    pub fn invalid_config(message: impl Into<String>) -> Self {
+        ManagerError::InvalidConfig { message: message.into() }
+    }
+
+    pub fn backend_unavailable(message: impl Into<String>) -> Self {
+        ManagerError::BackendUnavailable { message: message.into() }
+    }
+
+    /// A failure without an OS error code
+    pub fn other(message: impl Into<String>) -> Self {
+        ManagerError::Os { code: None, message: message.into() }
+    }
+
+    /// An OS error with its code, described as "<message>: <OS description>"
+    pub fn os(err: &io::Error, message: impl fmt::Display) -> Self {
+        ManagerError::Os { code: err.raw_os_error(), message: format!("{}: {}", message, err) }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            ManagerError::AccessDenied { message, .. }
+            | ManagerError::ProcessExited { message, .. }
+            | ManagerError::ProtectedProcess { message, .. }
+            | ManagerError::InvalidConfig { message }
+            | ManagerError::BackendUnavailable { message }
+            | ManagerError::Os { message, .. } => message,
+        }
+    }
+
+    /// The same error with context put in front of its message, e.g. "PID 42: Access denied ..."
+    pub fn context(mut self, context: impl fmt::Display) -> Self {
+        let message = match &mut self {
+            ManagerError::AccessDenied { message, .. }
+            | ManagerError::ProcessExited { message, .. }
+            | ManagerError::ProtectedProcess { message, .. }
+            | ManagerError::InvalidConfig { message }
+            | ManagerError::BackendUnavailable { message }
+            | ManagerError::Os { message, .. } => message,
+        };
+        *message = format!("{}: {}", context, message);
+        self
+    }
+
+    /// Benign: the process was gone before anything could be done to it
+    pub fn is_process_exited(&self) -> bool {
+        matches!(self, ManagerError::ProcessExited { .. })
+    }
+
+    /// Actionable: running elevated would let this succeed
+    pub fn needs_elevation(&self) -> bool {
+        matches!(self, ManagerError::AccessDenied { .. })
+    }
+}
+
+impl fmt::Display for ManagerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for ManagerError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn serialises_with_a_kind_tag() {
+        let err = ManagerError::access_denied(42, "Access denied to PID 42");
+        assert_eq!(serde_json::to_value(&err).unwrap(), json!({"kind": "access_denied", "pid": 42, "message": "Access denied to PID 42"}));
+        assert_eq!(
+            serde_json::to_value(ManagerError::other("Failed")).unwrap(),
+            json!({"kind": "os", "code": null, "message": "Failed"}),
+        );
+    }
+
+    #[test]
+    fn round_trips_every_kind() {
+        let errors = [
+            ManagerError::access_denied(None, "denied"),
+            ManagerError::process_exited(7, "gone"),
+            ManagerError::protected_process(1, "protected"),
+            ManagerError::invalid_config("bad rule"),
+            ManagerError::backend_unavailable("no daemon"),
+            ManagerError::os(&io::Error::from_raw_os_error(13), "Failed to write"),
+        ];
+        for err in errors {
+            let json = serde_json::to_string(&err).unwrap();
+            assert_eq!(serde_json::from_str::<ManagerError>(&json).unwrap(), err, "{}", json);
+        }
+    }
+
+    #[test]
+    fn keeps_the_os_error_code_and_description() {
+        let err = ManagerError::os(&io::Error::from_raw_os_error(13), "Failed to write");
+        let ManagerError::Os { code, message } = &err else {
+            panic!("{:?}", err);
+        };
+        assert_eq!(*code, Some(13));
+        assert!(message.starts_with("Failed to write: "), "{}", message);
+    }
+
+    #[test]
+    fn context_goes_in_front_of_the_message() {
+        let err = ManagerError::process_exited(42, "No such process").context("icad.exe (PID 42)");
+        assert_eq!(err.to_string(), "icad.exe (PID 42): No such process");
+        assert!(err.is_process_exited());
+        assert!(!err.needs_elevation());
+        assert!(ManagerError::access_denied(42, "denied").needs_elevation());
+    }
+}
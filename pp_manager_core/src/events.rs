@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+/// Something the monitor noticed or did, pushed to clients as it happens
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EngineEvent {
+    ProcessFound { rule: String, pid: u32 },
+    SettingsReapplied { rule: String, pid: u32, changes: String },
+    ProcessExited { rule: String, pid: u32 },
+    Error { message: String },
+    MonitoringStarted,
+    MonitoringStopped,
+}
+
+impl EngineEvent {
+    /// Name the event is published under, e.g. as a Tauri event
+    pub fn name(&self) -> &'static str {
+        match self {
+            EngineEvent::ProcessFound { .. } => "process-found",
+            EngineEvent::SettingsReapplied { .. } => "settings-reapplied",
+            EngineEvent::ProcessExited { .. } => "process-exited",
+            EngineEvent::Error { .. } => "engine-error",
+            EngineEvent::MonitoringStarted => "monitoring-started",
+            EngineEvent::MonitoringStopped => "monitoring-stopped",
+        }
+    }
+}
+
+/// Subscribers to engine events; a subscriber that drops its receiver is removed on the next event
+#[derive(Clone, Default)]
+pub struct EventBus {
+    subscribers: Arc<Mutex<Vec<Sender<EngineEvent>>>>,
+}
+
+impl EventBus {
+    pub fn subscribe(&self) -> Receiver<EngineEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    pub fn emit(&self, event: EngineEvent) {
+        self.subscribers.lock().unwrap().retain(|sender| sender.send(event.clone()).is_ok());
+    }
+
+    pub fn emit_all(&self, events: Vec<EngineEvent>) {
+        for event in events {
+            self.emit(event);
+        }
+    }
+}
@@ -0,0 +1,19 @@
+// Headless core of the process manager: rules and config, the monitoring engine and the
+// OS backends. The Tauri app and other tools are thin clients over `engine::Engine`.
+
+pub mod cgroup;
+pub mod config;
+#[cfg(target_os = "linux")]
+pub mod daemon;
+pub mod engine;
+pub mod error;
+pub mod events;
+pub mod log_files;
+pub mod log_throttle;
+pub mod logs;
+pub mod numa;
+pub mod platform;
+pub mod priority;
+pub mod process_manager;
+pub mod rpc;
+pub mod supervisor;
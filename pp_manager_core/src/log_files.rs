@@ -0,0 +1,314 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::config::LogFileConfig;
+use crate::error::ManagerError;
+use crate::logs::LogEntry;
+
+// Same identifier as the Tauri app, so logs land in its data directory
+const APP_IDENTIFIER: &str = "com.ppmanager.app";
+const FILE_PREFIX: &str = "pp_manager-";
+const FILE_EXTENSION: &str = "jsonl";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+/// Where an export was written, and how many entries it holds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogExport {
+    pub path: String,
+    pub entries: usize,
+}
+
+/// Where log files go when the config doesn't name a directory
+pub fn default_log_directory() -> PathBuf {
+    let data_dir = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))
+    };
+
+    data_dir.unwrap_or_else(env::temp_dir).join(APP_IDENTIFIER).join("logs")
+}
+
+pub fn log_directory(config: &LogFileConfig) -> PathBuf {
+    config.directory.as_ref().map_or_else(default_log_directory, PathBuf::from)
+}
+
+/// Log entries appended as JSON Lines to files named after the time they were started
+pub struct LogFiles {
+    directory: PathBuf,
+    max_file_bytes: u64,
+    max_age: Duration,
+    current: Option<CurrentFile>,
+}
+
+struct CurrentFile {
+    file: File,
+    size: u64,
+    day: NaiveDate,
+}
+
+impl LogFiles {
+    pub fn open(config: &LogFileConfig) -> Result<Self, ManagerError> {
+        let directory = log_directory(config);
+        fs::create_dir_all(&directory)
+            .map_err(|e| ManagerError::os(&e, format_args!("Failed to create log directory {}", directory.display())))?;
+
+        let log_files = Self {
+            directory,
+            max_file_bytes: config.max_file_size_kb.max(1) * 1024,
+            max_age: Duration::from_secs(u64::from(config.max_age_days) * 24 * 60 * 60),
+            current: None,
+        };
+        log_files.remove_expired();
+        Ok(log_files)
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn append(&mut self, entry: &LogEntry) -> Result<(), ManagerError> {
+        let mut line = serde_json::to_string(entry).map_err(|e| ManagerError::other(e.to_string()))?;
+        line.push('\n');
+
+        let today = entry.timestamp.date_naive();
+        let needs_new_file = self.current.as_ref().is_none_or(|current| {
+            current.day != today || current.size + line.len() as u64 > self.max_file_bytes
+        });
+        if needs_new_file {
+            self.start_file(entry.timestamp)?;
+        }
+
+        let Some(current) = self.current.as_mut() else {
+            return Ok(());
+        };
+        current.file.write_all(line.as_bytes()).map_err(|e| ManagerError::os(&e, "Failed to write log file"))?;
+        current.size += line.len() as u64;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), ManagerError> {
+        let Some(current) = self.current.as_mut() else {
+            return Ok(());
+        };
+        current.file.sync_data().map_err(|e| ManagerError::os(&e, "Failed to flush log file"))
+    }
+
+    fn start_file(&mut self, started: DateTime<Local>) -> Result<(), ManagerError> {
+        let name = format!("{}{}.{}", FILE_PREFIX, started.format("%Y%m%d-%H%M%S-%3f"), FILE_EXTENSION);
+        let path = self.directory.join(name);
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| ManagerError::os(&e, format_args!("Failed to open log file {}", path.display())))?;
+
+        self.current = Some(CurrentFile { file, size: 0, day: started.date_naive() });
+        self.remove_expired();
+        Ok(())
+    }
+
+    /// Delete files not written to within the retention period
+    fn remove_expired(&self) {
+        let now = SystemTime::now();
+        for path in log_file_paths(&self.directory) {
+            let expired = fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .is_some_and(|age| age > self.max_age);
+            if expired {
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
+}
+
+/// Log files in a directory, oldest first
+fn log_file_paths(directory: &Path) -> Vec<PathBuf> {
+    let Ok(dir_entries) = fs::read_dir(directory) else {
+        return Vec::new();
+    };
+
+    // Names sort by the time the file was started
+    let mut files: Vec<PathBuf> = dir_entries
+        .filter_map(|dir_entry| dir_entry.ok().map(|dir_entry| dir_entry.path()))
+        .filter(|path| {
+            path.extension().is_some_and(|extension| extension == FILE_EXTENSION)
+                && path.file_name().is_some_and(|name| name.to_string_lossy().starts_with(FILE_PREFIX))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Entries logged between two times, across every log file in a directory
+pub fn read_range(directory: &Path, since: Option<DateTime<Local>>, until: Option<DateTime<Local>>) -> Result<Vec<LogEntry>, ManagerError> {
+    let mut entries = Vec::new();
+    for path in log_file_paths(directory) {
+        let file = File::open(&path).map_err(|e| ManagerError::os(&e, format_args!("Failed to read log file {}", path.display())))?;
+        // A line cut short by a crash is skipped rather than failing the export
+        entries.extend(
+            BufReader::new(file)
+                .lines()
+                .map_while(Result::ok)
+                .filter_map(|line| serde_json::from_str::<LogEntry>(&line).ok())
+                .filter(|entry| since.is_none_or(|since| entry.timestamp >= since))
+                .filter(|entry| until.is_none_or(|until| entry.timestamp <= until)),
+        );
+    }
+
+    entries.sort_by_key(|entry| entry.timestamp);
+    Ok(entries)
+}
+
+/// Write entries to a file as CSV or a JSON array
+pub fn export(entries: &[LogEntry], format: ExportFormat, path: &Path) -> Result<(), ManagerError> {
+    let contents = match format {
+        ExportFormat::Json => serde_json::to_string_pretty(entries).map_err(|e| ManagerError::other(e.to_string()))?,
+        ExportFormat::Csv => {
+            let mut csv = String::from("timestamp,level,rule,pid,old_affinity,new_affinity,old_priority,new_priority,repeat_count,first_seen,last_seen,message\n");
+            for entry in entries {
+                let fields = [
+                    entry.timestamp.to_rfc3339(),
+                    entry.level.to_string(),
+                    entry.rule.clone().unwrap_or_default(),
+                    entry.pid.map(|pid| pid.to_string()).unwrap_or_default(),
+                    entry.old_affinity.map(|mask| format!("0x{:X}", mask)).unwrap_or_default(),
+                    entry.new_affinity.map(|mask| format!("0x{:X}", mask)).unwrap_or_default(),
+                    entry.old_priority.map(|priority| priority.to_string()).unwrap_or_default(),
+                    entry.new_priority.map(|priority| priority.to_string()).unwrap_or_default(),
+                    entry.repeat_count.map(|count| count.to_string()).unwrap_or_default(),
+                    entry.first_seen.map(|time| time.to_rfc3339()).unwrap_or_default(),
+                    entry.last_seen.map(|time| time.to_rfc3339()).unwrap_or_default(),
+                    entry.message.clone(),
+                ];
+                let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+                csv.push_str(&row.join(","));
+                csv.push('\n');
+            }
+            csv
+        }
+    };
+
+    fs::write(path, contents).map_err(|e| ManagerError::os(&e, format_args!("Failed to write {}", path.display())))
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+    use tempfile::TempDir;
+    use crate::logs::LogLevel;
+
+    fn open(directory: &TempDir, max_file_size_kb: u64) -> LogFiles {
+        LogFiles::open(&LogFileConfig {
+            enabled: true,
+            directory: Some(directory.path().display().to_string()),
+            max_file_size_kb,
+            max_age_days: 30,
+        })
+        .unwrap()
+    }
+
+    fn entry_at(message: &str, at: DateTime<Local>) -> LogEntry {
+        let mut entry = LogEntry::new(LogLevel::Info, message.to_string());
+        entry.timestamp = at;
+        entry
+    }
+
+    #[test]
+    fn starts_a_new_file_when_full_and_every_day() {
+        let directory = TempDir::new().unwrap();
+        let mut files = open(&directory, 1);
+        let start = Local::now() - TimeDelta::days(2);
+
+        // Three entries of about 400 bytes fill a 1 KiB file
+        for n in 0..3 {
+            files.append(&entry_at(&"x".repeat(300), start + TimeDelta::seconds(n))).unwrap();
+        }
+        assert_eq!(log_file_paths(directory.path()).len(), 2);
+
+        files.append(&entry_at("Next day", start + TimeDelta::days(1))).unwrap();
+        assert_eq!(log_file_paths(directory.path()).len(), 3);
+    }
+
+    #[test]
+    fn deletes_files_older_than_the_retention_period() {
+        let directory = TempDir::new().unwrap();
+        let old = directory.path().join("pp_manager-20200101-000000-000.jsonl");
+        let other = directory.path().join("notes.txt");
+        for path in [&old, &other] {
+            File::create(path).unwrap().set_modified(SystemTime::now() - Duration::from_secs(40 * 24 * 60 * 60)).unwrap();
+        }
+
+        open(&directory, 1024);
+        assert!(!old.exists());
+        assert!(other.exists());
+    }
+
+    #[test]
+    fn reads_a_time_range_across_files_in_order() {
+        let directory = TempDir::new().unwrap();
+        let mut files = open(&directory, 1);
+        let start = Local::now() - TimeDelta::hours(1);
+        for minute in [0, 1, 2, 3] {
+            files.append(&entry_at(&format!("{} {}", minute, "x".repeat(400)), start + TimeDelta::minutes(minute))).unwrap();
+        }
+        files.flush().unwrap();
+
+        // A line cut short by a crash is skipped
+        let mut last = fs::OpenOptions::new().append(true).open(log_file_paths(directory.path()).pop().unwrap()).unwrap();
+        last.write_all(b"{\"seq\":9,\"timest").unwrap();
+
+        let entries = read_range(directory.path(), Some(start + TimeDelta::minutes(1)), Some(start + TimeDelta::minutes(2))).unwrap();
+        let minutes: Vec<&str> = entries.iter().map(|entry| &entry.message[..1]).collect();
+        assert_eq!(minutes, ["1", "2"]);
+        assert_eq!(read_range(directory.path(), None, None).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn quotes_csv_fields_that_need_it() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a, b"), "\"a, b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn exports_csv_with_a_header_and_one_row_per_entry() {
+        let directory = TempDir::new().unwrap();
+        let path = directory.path().join("export.csv");
+        let entry = LogEntry::new(LogLevel::Reapply, "Affinity 0x1, then 0x2".to_string())
+            .with_process("icad.exe", 42)
+            .with_affinity(0x1, 0x2);
+
+        export(&[entry], ExportFormat::Csv, &path).unwrap();
+        let csv = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("timestamp,level,rule,pid,"));
+        assert!(lines[1].ends_with(",REAPPLY,icad.exe,42,0x1,0x2,,,,,,\"Affinity 0x1, then 0x2\""), "{}", lines[1]);
+    }
+}
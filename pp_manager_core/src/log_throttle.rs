@@ -0,0 +1,372 @@
+use std::collections::{BTreeSet, HashMap};
+use chrono::{DateTime, Local, TimeDelta};
+
+use crate::config::LogThrottleConfig;
+use crate::events::EngineEvent;
+use crate::logs::{LogEntry, LogLevel};
+use crate::process_manager::format_pid_list;
+
+// Rate limits count entries over fixed windows of this length
+const RATE_WINDOW: TimeDelta = TimeDelta::minutes(1);
+
+/// Identical entries count as repeats of each other, and so do errors that differ only in
+/// numbers, such as the same failure for another PID
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct RepeatKey {
+    level: LogLevel,
+    rule: Option<String>,
+    message: String,
+}
+
+impl RepeatKey {
+    fn of(entry: &LogEntry) -> Self {
+        Self {
+            level: entry.level,
+            rule: entry.rule.clone(),
+            message: match entry.level {
+                LogLevel::Error => without_numbers(&entry.message),
+                _ => entry.message.clone(),
+            },
+        }
+    }
+}
+
+fn without_numbers(message: &str) -> String {
+    let mut normalized = String::with_capacity(message.len());
+    for c in message.chars() {
+        if c.is_ascii_digit() {
+            if !normalized.ends_with('#') {
+                normalized.push('#');
+            }
+        } else {
+            normalized.push(c);
+        }
+    }
+    normalized
+}
+
+/// An entry being collapsed: it was logged once, and its repeats are counted until they stop
+struct Repeat {
+    latest: LogEntry,
+    first_seen: DateTime<Local>,
+    last_seen: DateTime<Local>,
+    last_reported: DateTime<Local>,
+    count: u32,
+    reported_count: u32,
+    // Every PID the repeats were about, as the latest entry only names one
+    pids: BTreeSet<u32>,
+}
+
+impl Repeat {
+    fn unreported(&self) -> u32 {
+        self.count - self.reported_count
+    }
+
+    /// The latest repeat, carrying the count and first/last timestamps so far
+    fn summary(&self, message: String) -> LogEntry {
+        let mut entry = LogEntry::new(self.latest.level, message);
+        entry.rule = self.latest.rule.clone();
+        entry.pid = if self.pids.len() > 1 { None } else { self.latest.pid };
+        entry.with_repeats(self.count, self.first_seen, self.last_seen)
+    }
+
+    /// " for PIDs 100, 200" when the repeats were about more than one process
+    fn pids_note(&self) -> String {
+        if self.pids.len() > 1 {
+            format!(" for PIDs {}", format_pid_list(&self.pids.iter().copied().collect::<Vec<_>>()))
+        } else {
+            String::new()
+        }
+    }
+}
+
+struct RateWindow {
+    started: DateTime<Local>,
+    admitted: u32,
+    dropped: u32,
+}
+
+/// Keeps the monitor from flooding the log: repeated entries are collapsed into one with a count,
+/// each rule gets a rate limit, and something that keeps repeating is summarised periodically
+pub struct LogThrottle {
+    config: LogThrottleConfig,
+    repeats: HashMap<RepeatKey, Repeat>,
+    // Keyed by rule; entries about no rule share the None window
+    rate_windows: HashMap<Option<String>, RateWindow>,
+    // When each error event was last published
+    error_events: HashMap<String, DateTime<Local>>,
+}
+
+impl LogThrottle {
+    pub fn new(config: LogThrottleConfig) -> Self {
+        Self {
+            config,
+            repeats: HashMap::new(),
+            rate_windows: HashMap::new(),
+            error_events: HashMap::new(),
+        }
+    }
+
+    fn repeat_window(&self) -> TimeDelta {
+        TimeDelta::seconds(self.config.repeat_window_seconds as i64)
+    }
+
+    /// The entries that should be logged; repeats and entries over a rate limit are held back
+    pub fn admit(&mut self, entries: Vec<LogEntry>) -> Vec<LogEntry> {
+        if !self.config.enabled {
+            return entries;
+        }
+        entries.into_iter().filter_map(|entry| self.admit_entry(entry)).collect()
+    }
+
+    fn admit_entry(&mut self, entry: LogEntry) -> Option<LogEntry> {
+        let now = entry.timestamp;
+        let key = RepeatKey::of(&entry);
+        let repeat_window = self.repeat_window();
+
+        if let Some(repeat) = self.repeats.get_mut(&key) {
+            if now - repeat.last_seen <= repeat_window {
+                repeat.count += 1;
+                repeat.last_seen = now;
+                repeat.pids.extend(entry.pid);
+                repeat.latest = entry;
+                return None;
+            }
+        }
+
+        let window = self.rate_windows.entry(entry.rule.clone()).or_insert(RateWindow {
+            started: now,
+            admitted: 0,
+            dropped: 0,
+        });
+        if window.admitted >= self.config.max_per_rule_per_minute {
+            window.dropped += 1;
+            return None;
+        }
+        window.admitted += 1;
+
+        self.repeats.insert(key, Repeat {
+            latest: entry.clone(),
+            first_seen: now,
+            last_seen: now,
+            last_reported: now,
+            count: 1,
+            reported_count: 1,
+            pids: entry.pid.into_iter().collect(),
+        });
+        Some(entry)
+    }
+
+    /// Whether to publish an event; an error already published within the repeat window is dropped,
+    /// as its log entry carries the count
+    pub fn admit_event(&mut self, event: &EngineEvent) -> bool {
+        let EngineEvent::Error { message } = event else {
+            return true;
+        };
+        if !self.config.enabled {
+            return true;
+        }
+
+        let now = Local::now();
+        let repeat_window = self.repeat_window();
+        let key = without_numbers(message);
+        match self.error_events.get(&key) {
+            Some(&published) if now - published <= repeat_window => false,
+            _ => {
+                self.error_events.insert(key, now);
+                true
+            }
+        }
+    }
+
+    /// Summaries that are due: repeats that stopped, ones still repeating after the summary interval,
+    /// and rate limit windows that held entries back. These are logged as they are, without throttling
+    pub fn flush(&mut self, now: DateTime<Local>) -> Vec<LogEntry> {
+        let repeat_window = self.repeat_window();
+        let summary_interval = TimeDelta::seconds(self.config.summary_interval_seconds as i64);
+        let mut summaries = Vec::new();
+
+        self.repeats.retain(|_, repeat| {
+            if now - repeat.last_seen > repeat_window {
+                if repeat.unreported() > 0 {
+                    summaries.push(repeat.summary(format!(
+                        "{} (repeated {} times{} between {} and {})",
+                        repeat.latest.message,
+                        repeat.count,
+                        repeat.pids_note(),
+                        repeat.first_seen.format("%H:%M:%S"),
+                        repeat.last_seen.format("%H:%M:%S"),
+                    )));
+                }
+                return false;
+            }
+
+            if repeat.unreported() > 0 && now - repeat.last_reported >= summary_interval {
+                let state = if repeat.latest.level == LogLevel::Error { "Still failing" } else { "Still repeating" };
+                summaries.push(repeat.summary(format!(
+                    "{}: {} ({} times{} since {})",
+                    state,
+                    repeat.latest.message,
+                    repeat.count,
+                    repeat.pids_note(),
+                    repeat.first_seen.format("%H:%M:%S"),
+                )));
+                repeat.reported_count = repeat.count;
+                repeat.last_reported = now;
+            }
+            true
+        });
+
+        self.rate_windows.retain(|rule, window| {
+            if now - window.started < RATE_WINDOW {
+                return true;
+            }
+            if window.dropped > 0 {
+                let subject = rule.as_deref().unwrap_or("the monitor");
+                let mut entry = LogEntry::new(
+                    LogLevel::Info,
+                    format!("Rate limit: {} log entries about {} held back in the last minute", window.dropped, subject),
+                );
+                entry.rule = rule.clone();
+                summaries.push(entry);
+            }
+            false
+        });
+
+        self.error_events.retain(|_, published| now - *published <= repeat_window);
+        summaries
+    }
+
+    /// Summaries of every repeat not yet reported, for when monitoring stops
+    pub fn finish(&mut self) -> Vec<LogEntry> {
+        let far_future = Local::now() + self.repeat_window() + RATE_WINDOW + TimeDelta::seconds(1);
+        self.flush(far_future)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn throttle() -> LogThrottle {
+        LogThrottle::new(LogThrottleConfig {
+            enabled: true,
+            repeat_window_seconds: 60,
+            max_per_rule_per_minute: 3,
+            summary_interval_seconds: 300,
+        })
+    }
+
+    fn start() -> DateTime<Local> {
+        Local::now() - TimeDelta::hours(1)
+    }
+
+    fn entry(level: LogLevel, message: &str, pid: u32, at: DateTime<Local>) -> LogEntry {
+        let mut entry = LogEntry::new(level, message.to_string()).with_process("game.exe", pid);
+        entry.timestamp = at;
+        entry
+    }
+
+    #[test]
+    fn collapses_repeats_and_summarises_them_once_they_stop() {
+        let mut throttle = throttle();
+        let start = start();
+        for second in 0..3 {
+            let admitted = throttle.admit(vec![entry(LogLevel::Reapply, "CPU affinity: 0x1 → 0x2", 100, start + TimeDelta::seconds(second))]);
+            assert_eq!(admitted.len(), usize::from(second == 0));
+        }
+
+        assert!(throttle.flush(start + TimeDelta::seconds(30)).is_empty());
+        let summaries = throttle.flush(start + TimeDelta::seconds(63));
+        assert_eq!(summaries.len(), 1);
+        assert!(summaries[0].message.starts_with("CPU affinity: 0x1 → 0x2 (repeated 3 times between"), "{}", summaries[0].message);
+        assert_eq!(summaries[0].repeat_count, Some(3));
+        assert_eq!(summaries[0].pid, Some(100));
+        assert!(throttle.flush(start + TimeDelta::seconds(200)).is_empty());
+    }
+
+    #[test]
+    fn only_errors_differing_in_numbers_count_as_repeats() {
+        let mut throttle = LogThrottle::new(LogThrottleConfig::default());
+        let start = start();
+
+        let found = throttle.admit(vec![
+            entry(LogLevel::Success, "Found PID 100", 100, start),
+            entry(LogLevel::Success, "Found PID 200", 200, start),
+            entry(LogLevel::Reapply, "CPU affinity: 0x1 → 0x2", 100, start),
+            entry(LogLevel::Reapply, "CPU affinity: 0x1 → 0x4", 100, start),
+        ]);
+        assert_eq!(found.len(), 4);
+
+        let failed = throttle.admit(vec![
+            entry(LogLevel::Error, "Failed to reapply (PID 100): access denied", 100, start),
+            entry(LogLevel::Error, "Failed to reapply (PID 200): access denied", 200, start + TimeDelta::seconds(1)),
+        ]);
+        assert_eq!(failed.len(), 1);
+
+        let summaries = throttle.flush(start + TimeDelta::seconds(62));
+        assert_eq!(summaries.len(), 1);
+        assert!(summaries[0].message.contains("repeated 2 times for PIDs 100, 200 between"), "{}", summaries[0].message);
+        assert_eq!(summaries[0].pid, None);
+    }
+
+    #[test]
+    fn reports_ongoing_repeats_every_summary_interval() {
+        let mut throttle = throttle();
+        let start = start();
+        for second in (0..=300).step_by(30) {
+            throttle.admit(vec![entry(LogLevel::Error, "Failed to reapply", 100, start + TimeDelta::seconds(second))]);
+        }
+
+        let summaries = throttle.flush(start + TimeDelta::seconds(300));
+        assert_eq!(summaries.len(), 1);
+        assert!(summaries[0].message.starts_with("Still failing: Failed to reapply (11 times since"), "{}", summaries[0].message);
+
+        // Nothing new to report until the next repeat
+        assert!(throttle.flush(start + TimeDelta::seconds(301)).is_empty());
+    }
+
+    #[test]
+    fn rate_limits_each_rule_per_minute() {
+        let mut throttle = throttle();
+        let start = start();
+        let entries: Vec<LogEntry> = (0..5)
+            .map(|n| entry(LogLevel::Reapply, &format!("Change {}", n), 100, start + TimeDelta::seconds(n)))
+            .collect();
+        assert_eq!(throttle.admit(entries).len(), 3);
+
+        let other_rule = LogEntry { rule: Some("other.exe".to_string()), ..entry(LogLevel::Reapply, "Change", 1, start) };
+        assert_eq!(throttle.admit(vec![other_rule]).len(), 1);
+
+        let summaries = throttle.flush(start + TimeDelta::seconds(61));
+        let held_back: Vec<&LogEntry> = summaries.iter().filter(|entry| entry.message.starts_with("Rate limit")).collect();
+        assert_eq!(held_back.len(), 1);
+        assert_eq!(held_back[0].message, "Rate limit: 2 log entries about game.exe held back in the last minute");
+    }
+
+    #[test]
+    fn finish_reports_every_unreported_repeat() {
+        let mut throttle = throttle();
+        let now = Local::now();
+        throttle.admit(vec![
+            entry(LogLevel::Error, "Failed to reapply", 100, now),
+            entry(LogLevel::Error, "Failed to reapply", 100, now),
+            entry(LogLevel::Monitor, "Monitoring 1 processes", 100, now),
+        ]);
+
+        let summaries = throttle.finish();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].repeat_count, Some(2));
+        assert!(throttle.finish().is_empty());
+    }
+
+    #[test]
+    fn passes_everything_through_when_disabled() {
+        let mut throttle = LogThrottle::new(LogThrottleConfig { enabled: false, ..LogThrottleConfig::default() });
+        let start = start();
+        let entries = vec![entry(LogLevel::Error, "Failed", 100, start); 50];
+        assert_eq!(throttle.admit(entries).len(), 50);
+        assert!(throttle.admit_event(&EngineEvent::Error { message: "Failed".to_string() }));
+        assert!(throttle.admit_event(&EngineEvent::Error { message: "Failed".to_string() }));
+    }
+}
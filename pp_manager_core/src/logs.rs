@@ -0,0 +1,370 @@
+use std::collections::VecDeque;
+use std::fmt;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::error::ManagerError;
+use crate::log_files::LogFiles;
+use crate::priority::Priority;
+
+// Entries kept in memory when the config doesn't say otherwise
+pub const DEFAULT_LOG_CAPACITY: usize = 1000;
+
+/// Kind of a log entry; serialised in upper case, e.g. "REAPPLY"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum LogLevel {
+    Error,
+    Success,
+    Reapply,
+    Monitor,
+    Info,
+    Reserve,
+    Demote,
+    Watchdog,
+    Realtime,
+    Cgroup,
+    Suspend,
+    Instance,
+}
+
+impl LogLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Error => "ERROR",
+            LogLevel::Success => "SUCCESS",
+            LogLevel::Reapply => "REAPPLY",
+            LogLevel::Monitor => "MONITOR",
+            LogLevel::Info => "INFO",
+            LogLevel::Reserve => "RESERVE",
+            LogLevel::Demote => "DEMOTE",
+            LogLevel::Watchdog => "WATCHDOG",
+            LogLevel::Realtime => "REALTIME",
+            LogLevel::Cgroup => "CGROUP",
+            LogLevel::Suspend => "SUSPEND",
+            LogLevel::Instance => "INSTANCE",
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    // Position in the engine's log, assigned when the entry is stored
+    #[serde(default)]
+    pub seq: u64,
+    pub timestamp: DateTime<Local>,
+    pub message: String,
+    pub level: LogLevel,
+    // Structured details, when the entry is about one process
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_affinity: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_affinity: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_priority: Option<Priority>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_priority: Option<Priority>,
+    // Set when the entry stands for repeats of the same entry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat_count: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_seen: Option<DateTime<Local>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<DateTime<Local>>,
+}
+
+impl LogEntry {
+    pub fn new(level: LogLevel, message: String) -> Self {
+        Self {
+            seq: 0,
+            timestamp: Local::now(),
+            message,
+            level,
+            rule: None,
+            pid: None,
+            old_affinity: None,
+            new_affinity: None,
+            old_priority: None,
+            new_priority: None,
+            repeat_count: None,
+            first_seen: None,
+            last_seen: None,
+        }
+    }
+
+    pub fn with_rule(mut self, rule: &str) -> Self {
+        self.rule = Some(rule.to_string());
+        self
+    }
+
+    pub fn with_process(mut self, rule: &str, pid: u32) -> Self {
+        self.pid = Some(pid);
+        self.with_rule(rule)
+    }
+
+    pub fn with_affinity(mut self, old: u64, new: u64) -> Self {
+        self.old_affinity = Some(old);
+        self.new_affinity = Some(new);
+        self
+    }
+
+    pub fn with_priority(mut self, old: Priority, new: Priority) -> Self {
+        self.old_priority = Some(old);
+        self.new_priority = Some(new);
+        self
+    }
+
+    pub fn with_repeats(mut self, count: u32, first_seen: DateTime<Local>, last_seen: DateTime<Local>) -> Self {
+        self.repeat_count = Some(count);
+        self.first_seen = Some(first_seen);
+        self.last_seen = Some(last_seen);
+        self
+    }
+}
+
+/// Filter for query_logs; fields left empty match every entry
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogQuery {
+    pub levels: Vec<LogLevel>,
+    pub rule: Option<String>,
+    pub pid: Option<u32>,
+    pub since: Option<DateTime<Local>>,
+    pub until: Option<DateTime<Local>>,
+    // Only the newest matches, when set
+    pub limit: Option<usize>,
+}
+
+impl LogQuery {
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        (self.levels.is_empty() || self.levels.contains(&entry.level))
+            && self.rule.as_ref().is_none_or(|rule| entry.rule.as_ref().is_some_and(|r| r.eq_ignore_ascii_case(rule)))
+            && self.pid.is_none_or(|pid| entry.pid == Some(pid))
+            && self.since.is_none_or(|since| entry.timestamp >= since)
+            && self.until.is_none_or(|until| entry.timestamp <= until)
+    }
+}
+
+/// The engine's log: the newest entries up to a capacity, and every entry in the log files
+/// when they are enabled. Entries are numbered as they arrive, so clients can ask for only what's new
+pub struct LogStore {
+    entries: VecDeque<LogEntry>,
+    capacity: usize,
+    next_seq: u64,
+    files: Option<LogFiles>,
+    // Whether the last write to the log files failed, so the failure is reported once
+    files_failing: bool,
+}
+
+impl LogStore {
+    pub fn new(capacity: usize) -> Self {
+        // Sequence numbers start at 1, so a cursor of 0 means "everything"
+        Self {
+            entries: VecDeque::new(),
+            capacity: capacity.max(1),
+            next_seq: 1,
+            files: None,
+            files_failing: false,
+        }
+    }
+
+    pub fn push(&mut self, entry: LogEntry) {
+        let written = self.files.as_mut().map(|files| files.append(&entry));
+        self.keep(entry);
+
+        match written {
+            Some(Ok(())) => self.files_failing = false,
+            Some(Err(err)) => self.report_files_failure(err),
+            None => {}
+        }
+    }
+
+    /// Number an entry and keep it in memory only
+    fn keep(&mut self, mut entry: LogEntry) {
+        entry.seq = self.next_seq;
+        self.next_seq += 1;
+        self.entries.push_back(entry);
+        self.trim();
+    }
+
+    /// Log the first failure of the log files in memory, where clients can see it; later ones
+    /// are left out until a write succeeds again
+    fn report_files_failure(&mut self, err: ManagerError) {
+        if !self.files_failing {
+            self.files_failing = true;
+            self.keep(LogEntry::new(LogLevel::Error, format!("Log files are not being written: {}", err)));
+        }
+    }
+
+    pub fn set_files(&mut self, files: Option<LogFiles>) {
+        self.files = files;
+        self.files_failing = false;
+    }
+
+    /// Make sure every entry written to the log files has reached the disk
+    pub fn flush(&mut self) {
+        if let Some(Err(err)) = self.files.as_mut().map(LogFiles::flush) {
+            self.report_files_failure(err);
+        }
+    }
+
+    pub fn files(&self) -> Option<&LogFiles> {
+        self.files.as_ref()
+    }
+
+    pub fn extend(&mut self, entries: impl IntoIterator<Item = LogEntry>) {
+        for entry in entries {
+            self.push(entry);
+        }
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.entries.len() > self.capacity {
+            self.entries.pop_front();
+        }
+    }
+
+    pub fn entries(&self) -> Vec<LogEntry> {
+        self.entries.iter().cloned().collect()
+    }
+
+    /// Entries logged after the one numbered `seq`; older entries may have been dropped already
+    pub fn since(&self, seq: u64) -> Vec<LogEntry> {
+        let start = self.entries.partition_point(|entry| entry.seq <= seq);
+        self.entries.range(start..).cloned().collect()
+    }
+
+    /// Entries matching a query, oldest first
+    pub fn query(&self, query: &LogQuery) -> Vec<LogEntry> {
+        let mut matches: Vec<LogEntry> = self.entries.iter().filter(|entry| query.matches(entry)).cloned().collect();
+        if let Some(limit) = query.limit {
+            matches.drain(..matches.len().saturating_sub(limit));
+        }
+        matches
+    }
+
+    /// Number of the newest entry, or 0 if nothing was logged yet
+    pub fn last_seq(&self) -> u64 {
+        self.next_seq - 1
+    }
+
+    /// Drop every entry kept in memory; the log files keep theirs, and numbering continues,
+    /// so existing cursors stay valid
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+impl Default for LogStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_LOG_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    fn store_with(capacity: usize, count: usize) -> LogStore {
+        let mut store = LogStore::new(capacity);
+        store.extend((1..=count).map(|n| LogEntry::new(LogLevel::Info, format!("Entry {}", n))));
+        store
+    }
+
+    fn seqs(entries: &[LogEntry]) -> Vec<u64> {
+        entries.iter().map(|entry| entry.seq).collect()
+    }
+
+    #[test]
+    fn keeps_the_newest_entries_up_to_the_capacity() {
+        let mut store = store_with(3, 5);
+        assert_eq!(seqs(&store.entries()), [3, 4, 5]);
+        assert_eq!(store.last_seq(), 5);
+
+        store.set_capacity(2);
+        assert_eq!(seqs(&store.entries()), [4, 5]);
+    }
+
+    #[test]
+    fn returns_entries_after_a_sequence_number() {
+        let store = store_with(3, 5);
+        assert_eq!(seqs(&store.since(0)), [3, 4, 5]);
+        assert_eq!(seqs(&store.since(3)), [4, 5]);
+        assert!(store.since(5).is_empty());
+    }
+
+    #[test]
+    fn numbering_continues_after_clearing() {
+        let mut store = store_with(10, 2);
+        store.clear();
+        assert!(store.entries().is_empty());
+
+        store.push(LogEntry::new(LogLevel::Info, "After".to_string()));
+        assert_eq!(seqs(&store.since(2)), [3]);
+    }
+
+    #[test]
+    fn reports_failing_log_files_once_in_memory() {
+        let directory = tempfile::TempDir::new().unwrap();
+        let logs = directory.path().join("logs");
+        let config = crate::config::LogFileConfig { directory: Some(logs.display().to_string()), ..Default::default() };
+        let mut store = LogStore::new(10);
+        store.set_files(Some(LogFiles::open(&config).unwrap()));
+
+        std::fs::remove_dir(&logs).unwrap();
+        store.extend((1..=3).map(|n| LogEntry::new(LogLevel::Info, format!("Entry {}", n))));
+
+        let entries = store.entries();
+        let messages: Vec<&str> = entries.iter().map(|entry| entry.message.as_str()).collect();
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[0], "Entry 1");
+        assert!(messages[1].starts_with("Log files are not being written: "), "{}", messages[1]);
+        assert_eq!(&messages[2..], ["Entry 2", "Entry 3"]);
+        assert_eq!(entries[1].level, LogLevel::Error);
+        assert_eq!(seqs(&entries), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn queries_by_level_rule_pid_and_time() {
+        let start = Local::now() - TimeDelta::hours(1);
+        let mut store = LogStore::new(10);
+        for (minute, level, rule, pid) in [
+            (0, LogLevel::Reapply, "icad.exe", 1),
+            (1, LogLevel::Error, "icad.exe", 2),
+            (2, LogLevel::Reapply, "game.exe", 3),
+            (3, LogLevel::Reapply, "icad.exe", 4),
+        ] {
+            let mut entry = LogEntry::new(level, format!("Entry {}", minute)).with_process(rule, pid);
+            entry.timestamp = start + TimeDelta::minutes(minute);
+            store.push(entry);
+        }
+        store.push(LogEntry::new(LogLevel::Monitor, "No rule".to_string()));
+
+        let query = |query: LogQuery| seqs(&store.query(&query));
+        assert_eq!(query(LogQuery::default()), [1, 2, 3, 4, 5]);
+        assert_eq!(query(LogQuery { levels: vec![LogLevel::Reapply], ..LogQuery::default() }), [1, 3, 4]);
+        assert_eq!(query(LogQuery { rule: Some("ICAD.EXE".to_string()), ..LogQuery::default() }), [1, 2, 4]);
+        assert_eq!(query(LogQuery { pid: Some(3), ..LogQuery::default() }), [3]);
+        assert_eq!(
+            query(LogQuery { since: Some(start + TimeDelta::minutes(1)), until: Some(start + TimeDelta::minutes(2)), ..LogQuery::default() }),
+            [2, 3],
+        );
+        assert_eq!(query(LogQuery { levels: vec![LogLevel::Reapply], limit: Some(2), ..LogQuery::default() }), [3, 4]);
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use serde::{Serialize, Deserialize};
+
+/// How a rule wants its processes' memory placed across NUMA nodes. Only `Bind` can be applied
+/// to a running process; the others are a memory policy the process has to set itself, so they
+/// are recognised only to be refused with an explanation. `Bind` is enforced by migrating pages
+/// each cycle, so without cgroup limits new allocations can land on other nodes until they are
+/// moved; a rule with cgroup limits also confines allocations through `cpuset.mems`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum NumaMode {
+    // Memory only on the given nodes
+    #[serde(alias = "bind")]
+    Bind,
+    // Memory on a single node, spilling elsewhere only when it is full
+    #[serde(alias = "preferred", alias = "prefer")]
+    Preferred,
+    // Memory spread across the given nodes
+    #[serde(alias = "interleave")]
+    Interleave,
+}
+
+/// NUMA settings as written in the config
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NumaConfig {
+    pub mode: NumaMode,
+    // Nodes to place memory on; empty means the nodes that hold the rule's selected cores
+    #[serde(default)]
+    pub nodes: Vec<u32>,
+}
+
+/// A NUMA mode resolved against the machine's topology
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct NumaPlacement {
+    pub mode: NumaMode,
+    pub node_mask: u64,
+}
+
+impl NumaConfig {
+    /// Resolve the configured nodes, defaulting to those whose CPUs overlap the affinity mask;
+    /// `topology` lists each node with the mask of its CPUs
+    pub fn resolve(&self, affinity_mask: u64, topology: &[(u32, u64)]) -> Result<NumaPlacement, String> {
+        if self.mode != NumaMode::Bind {
+            return Err(format!(
+                "{:?} NUMA placement is a memory policy only the process itself can set; start it under numactl, or use Bind",
+                self.mode
+            ));
+        }
+
+        let mut node_mask = 0u64;
+
+        if self.nodes.is_empty() {
+            for (node, cpu_mask) in topology {
+                if cpu_mask & affinity_mask != 0 {
+                    node_mask |= 1u64 << node;
+                }
+            }
+            if node_mask == 0 {
+                return Err("no NUMA node holds the selected cores".to_string());
+            }
+        } else {
+            for &node in &self.nodes {
+                if !topology.iter().any(|(known, _)| *known == node) {
+                    return Err(format!("unknown NUMA node {}", node));
+                }
+                node_mask |= 1u64 << node;
+            }
+        }
+
+        Ok(NumaPlacement { mode: self.mode, node_mask })
+    }
+}
+
+impl fmt::Display for NumaPlacement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let nodes: Vec<String> = (0..64)
+            .filter(|node| self.node_mask & (1u64 << node) != 0)
+            .map(|node| node.to_string())
+            .collect();
+        write!(f, "{:?}({})", self.mode, nodes.join(","))
+    }
+}
+
+/// Where a process's memory currently is, from the kernel's per-mapping NUMA statistics
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NumaUsage {
+    // Distinct memory policies of the process's mappings, e.g. "default" or "bind:1"
+    pub policies: Vec<String>,
+    pub pages_per_node: BTreeMap<u32, u64>,
+}
+
+impl NumaUsage {
+    /// Pages that sit on nodes outside a mask
+    pub fn pages_outside(&self, node_mask: u64) -> u64 {
+        self.pages_per_node
+            .iter()
+            .filter(|(node, _)| **node >= 64 || node_mask & (1u64 << **node) == 0)
+            .map(|(_, pages)| pages)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two nodes of four CPUs each
+    const TOPOLOGY: [(u32, u64); 2] = [(0, 0x0F), (1, 0xF0)];
+
+    fn bind(nodes: Vec<u32>) -> NumaConfig {
+        NumaConfig { mode: NumaMode::Bind, nodes }
+    }
+
+    #[test]
+    fn defaults_to_the_nodes_of_the_selected_cores() {
+        let placement = bind(Vec::new()).resolve(0x30, &TOPOLOGY).unwrap();
+        assert_eq!(placement, NumaPlacement { mode: NumaMode::Bind, node_mask: 0b10 });
+
+        let placement = bind(Vec::new()).resolve(0x18, &TOPOLOGY).unwrap();
+        assert_eq!(placement.node_mask, 0b11);
+        assert_eq!(placement.to_string(), "Bind(0,1)");
+
+        assert!(bind(Vec::new()).resolve(0x100, &TOPOLOGY).is_err());
+    }
+
+    #[test]
+    fn checks_configured_nodes_against_the_topology() {
+        assert_eq!(bind(vec![1]).resolve(0x01, &TOPOLOGY).unwrap().node_mask, 0b10);
+        assert!(bind(vec![2]).resolve(0x01, &TOPOLOGY).is_err());
+    }
+
+    #[test]
+    fn refuses_policies_a_process_must_set_itself() {
+        for mode in [NumaMode::Preferred, NumaMode::Interleave] {
+            let config = NumaConfig { mode, nodes: vec![0] };
+            assert!(config.resolve(0x01, &TOPOLOGY).is_err(), "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn counts_pages_outside_the_mask() {
+        let usage = NumaUsage {
+            policies: vec!["default".to_string()],
+            pages_per_node: BTreeMap::from([(0, 10), (1, 5), (70, 1)]),
+        };
+        assert_eq!(usage.pages_outside(0b01), 6);
+        assert_eq!(usage.pages_outside(0b11), 1);
+    }
+}
@@ -0,0 +1,607 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::mem;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::ManagerError;
+use crate::numa::NumaUsage;
+use crate::priority::{IoPriority, Priority, Scheduling, SchedulingPolicy};
+
+pub const SUPPORTS_SCHEDULING_POLICY: bool = true;
+pub const SUPPORTS_IO_PRIORITY: bool = true;
+pub const SUPPORTS_CGROUPS: bool = true;
+pub const SUPPORTS_OOM_SCORE_ADJ: bool = true;
+pub const SUPPORTS_NUMA: bool = true;
+pub const SUPPORTS_THREAD_RULES: bool = true;
+
+// ioprio_get/ioprio_set have no libc wrappers; these mirror linux/ioprio.h
+const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+const IOPRIO_CLASS_NONE: libc::c_int = 0;
+const IOPRIO_CLASS_RT: libc::c_int = 1;
+const IOPRIO_CLASS_BE: libc::c_int = 2;
+const IOPRIO_CLASS_IDLE: libc::c_int = 3;
+
+/// Map a priority to a nice value
+pub fn nice_value(priority: Priority) -> i32 {
+    match priority {
+        Priority::Idle => 19,
+        Priority::BelowNormal => 10,
+        Priority::Normal => 0,
+        Priority::AboveNormal => -5,
+        Priority::High => -10,
+        Priority::Realtime => -20,
+    }
+}
+
+/// Map a nice value back to the closest priority
+pub fn priority_from_nice(nice: i32) -> Priority {
+    match nice {
+        15.. => Priority::Idle,
+        5..=14 => Priority::BelowNormal,
+        -2..=4 => Priority::Normal,
+        -7..=-3 => Priority::AboveNormal,
+        -15..=-8 => Priority::High,
+        _ => Priority::Realtime,
+    }
+}
+
+fn native_policy(policy: SchedulingPolicy) -> libc::c_int {
+    match policy {
+        SchedulingPolicy::Other => libc::SCHED_OTHER,
+        SchedulingPolicy::Batch => libc::SCHED_BATCH,
+        SchedulingPolicy::Idle => libc::SCHED_IDLE,
+        SchedulingPolicy::Fifo => libc::SCHED_FIFO,
+        SchedulingPolicy::RoundRobin => libc::SCHED_RR,
+    }
+}
+
+fn policy_from_native(policy: libc::c_int) -> Option<SchedulingPolicy> {
+    match policy & !libc::SCHED_RESET_ON_FORK {
+        libc::SCHED_OTHER => Some(SchedulingPolicy::Other),
+        libc::SCHED_BATCH => Some(SchedulingPolicy::Batch),
+        libc::SCHED_IDLE => Some(SchedulingPolicy::Idle),
+        libc::SCHED_FIFO => Some(SchedulingPolicy::Fifo),
+        libc::SCHED_RR => Some(SchedulingPolicy::RoundRobin),
+        _ => None,
+    }
+}
+
+fn native_io_priority(io_priority: IoPriority) -> libc::c_int {
+    let (class, level) = match io_priority {
+        IoPriority::Realtime(level) => (IOPRIO_CLASS_RT, level),
+        IoPriority::BestEffort(level) => (IOPRIO_CLASS_BE, level),
+        IoPriority::Idle => (IOPRIO_CLASS_IDLE, 0),
+    };
+    (class << IOPRIO_CLASS_SHIFT) | level as libc::c_int
+}
+
+fn io_priority_from_native(value: libc::c_int, nice: i32) -> Option<IoPriority> {
+    let level = (value & ((1 << IOPRIO_CLASS_SHIFT) - 1)) as u8;
+    match value >> IOPRIO_CLASS_SHIFT {
+        // Without an explicit class the kernel derives a best-effort level from the nice value
+        IOPRIO_CLASS_NONE => Some(IoPriority::BestEffort(((nice + 20) / 5).clamp(0, 7) as u8)),
+        IOPRIO_CLASS_RT => Some(IoPriority::Realtime(level)),
+        IOPRIO_CLASS_BE => Some(IoPriority::BestEffort(level)),
+        IOPRIO_CLASS_IDLE => Some(IoPriority::Idle),
+        _ => None,
+    }
+}
+
+fn errno_error(err: io::Error, action: &str, pid: u32) -> ManagerError {
+    match err.raw_os_error() {
+        Some(libc::ESRCH) | Some(libc::ENOENT) => {
+            ManagerError::process_exited(pid, format!("Failed to {} for PID {}: process has exited", action, pid))
+        }
+        Some(libc::EPERM) | Some(libc::EACCES) => ManagerError::access_denied(
+            pid,
+            format!("Access denied to {} for PID {}. Run as root or grant CAP_SYS_NICE.", action, pid),
+        ),
+        _ => ManagerError::os(&err, format_args!("Failed to {} for PID {}", action, pid)),
+    }
+}
+
+/// A process identified by PID; Linux needs no open handle
+pub struct ProcessHandle {
+    pid: u32,
+}
+
+impl ProcessHandle {
+    fn existing(pid: u32) -> Option<Self> {
+        Path::new(&format!("/proc/{}", pid)).exists().then_some(Self { pid })
+    }
+
+    /// Look up a process for changing its settings, without diagnostics
+    pub fn try_open_for_update(pid: u32) -> Option<Self> {
+        Self::existing(pid)
+    }
+
+    /// Look up a process for changing its settings; permission problems surface when applying
+    pub fn open_for_update(pid: u32) -> Result<Self, ManagerError> {
+        Self::existing(pid).ok_or_else(|| ManagerError::process_exited(pid, format!("Failed to open process PID {}: process has exited", pid)))
+    }
+
+    /// Look up a process for reading its settings only
+    pub fn open_for_query(pid: u32) -> Option<Self> {
+        Self::existing(pid)
+    }
+
+    /// Look up a process so it can be suspended and resumed
+    pub fn open_for_suspend(pid: u32) -> Result<Self, ManagerError> {
+        Self::existing(pid).ok_or_else(|| {
+            ManagerError::process_exited(pid, format!("Failed to open process PID {} for suspension. Process may have already exited.", pid))
+        })
+    }
+
+    /// Look up a process so it can be terminated
+    pub fn open_for_terminate(pid: u32) -> Result<Self, ManagerError> {
+        Self::existing(pid).ok_or_else(|| {
+            ManagerError::process_exited(pid, format!("Failed to open process PID {} for termination. Process may have already exited.", pid))
+        })
+    }
+
+    /// Thread IDs of the process; scheduling settings are per thread on Linux
+    pub fn thread_ids(&self) -> Vec<u32> {
+        let mut tids: Vec<u32> = fs::read_dir(format!("/proc/{}/task", self.pid))
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if tids.is_empty() {
+            tids.push(self.pid);
+        }
+        tids
+    }
+
+    /// Threads of the process with their names from /proc/<pid>/task/<tid>/comm
+    pub fn threads(&self) -> Vec<(u32, String)> {
+        self.thread_ids()
+            .into_iter()
+            .filter_map(|tid| {
+                let comm = fs::read_to_string(format!("/proc/{}/task/{}/comm", self.pid, tid)).ok()?;
+                Some((tid, comm.trim_end().to_string()))
+            })
+            .collect()
+    }
+
+    pub fn thread_affinity(&self, tid: u32) -> Result<u64, ManagerError> {
+        get_thread_affinity(tid).map_err(|err| errno_error(err, "get thread CPU affinity", tid))
+    }
+
+    /// Change one thread's affinity; a thread that has exited is not an error
+    pub fn set_thread_affinity(&self, tid: u32, affinity_mask: u64) -> Result<(), ManagerError> {
+        match set_thread_affinity(tid, affinity_mask) {
+            Err(err) if err.raw_os_error() != Some(libc::ESRCH) => Err(errno_error(err, "set thread CPU affinity", tid)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn thread_priority(&self, tid: u32) -> Result<Priority, ManagerError> {
+        get_thread_nice(tid)
+            .map(priority_from_nice)
+            .map_err(|err| errno_error(err, "get thread priority", tid))
+    }
+
+    /// Change one thread's nice value; a thread that has exited is not an error
+    pub fn set_thread_priority(&self, tid: u32, priority: Priority) -> Result<(), ManagerError> {
+        match set_thread_nice(tid, nice_value(priority)) {
+            Err(err) if err.raw_os_error() != Some(libc::ESRCH) => Err(errno_error(err, "set thread priority", tid)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn affinity(&self) -> Result<u64, ManagerError> {
+        get_thread_affinity(self.pid).map_err(|err| errno_error(err, "get CPU affinity", self.pid))
+    }
+
+    /// Move every thread except those a thread rule has claimed; threads exiting meanwhile are not an error
+    pub fn set_affinity(&self, affinity_mask: u64, claimed_tids: &[u32]) -> Result<(), ManagerError> {
+        for tid in self.thread_ids().into_iter().filter(|tid| !claimed_tids.contains(tid)) {
+            match set_thread_affinity(tid, affinity_mask) {
+                Err(err) if err.raw_os_error() != Some(libc::ESRCH) => {
+                    return Err(errno_error(err, "set CPU affinity", self.pid));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub fn priority(&self) -> Result<Priority, ManagerError> {
+        get_thread_nice(self.pid)
+            .map(priority_from_nice)
+            .map_err(|err| errno_error(err, "get priority", self.pid))
+    }
+
+    /// Renice every thread except those a thread rule has claimed
+    pub fn set_priority(&self, priority: Priority, claimed_tids: &[u32]) -> Result<(), ManagerError> {
+        let nice = nice_value(priority);
+        for tid in self.thread_ids().into_iter().filter(|tid| !claimed_tids.contains(tid)) {
+            match set_thread_nice(tid, nice) {
+                Err(err) if err.raw_os_error() != Some(libc::ESRCH) => {
+                    return Err(errno_error(err, "set priority", self.pid));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub fn scheduling(&self) -> Result<Scheduling, ManagerError> {
+        unsafe {
+            let native = libc::sched_getscheduler(self.pid as libc::pid_t);
+            if native < 0 {
+                return Err(errno_error(io::Error::last_os_error(), "get scheduling policy", self.pid));
+            }
+
+            let mut param: libc::sched_param = mem::zeroed();
+            if libc::sched_getparam(self.pid as libc::pid_t, &mut param) != 0 {
+                return Err(errno_error(io::Error::last_os_error(), "get scheduling policy", self.pid));
+            }
+
+            let policy = policy_from_native(native)
+                .ok_or_else(|| ManagerError::other(format!("Unknown scheduling policy {} for PID {}", native, self.pid)))?;
+            Ok(Scheduling { policy, rt_priority: param.sched_priority as u8 })
+        }
+    }
+
+    pub fn set_scheduling(&self, scheduling: Scheduling) -> Result<(), ManagerError> {
+        let param = libc::sched_param { sched_priority: scheduling.rt_priority as libc::c_int };
+        for tid in self.thread_ids() {
+            if unsafe { libc::sched_setscheduler(tid as libc::pid_t, native_policy(scheduling.policy), &param) } != 0 {
+                let err = io::Error::last_os_error();
+                if err.raw_os_error() != Some(libc::ESRCH) {
+                    return Err(errno_error(err, "set scheduling policy", self.pid));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn io_priority(&self) -> Result<IoPriority, ManagerError> {
+        let value = unsafe { libc::syscall(libc::SYS_ioprio_get, IOPRIO_WHO_PROCESS, self.pid as libc::c_int) };
+        if value < 0 {
+            return Err(errno_error(io::Error::last_os_error(), "get I/O priority", self.pid));
+        }
+
+        let nice = get_thread_nice(self.pid).unwrap_or(0);
+        io_priority_from_native(value as libc::c_int, nice)
+            .ok_or_else(|| ManagerError::other(format!("Unknown I/O priority 0x{:X} for PID {}", value, self.pid)))
+    }
+
+    pub fn set_io_priority(&self, io_priority: IoPriority) -> Result<(), ManagerError> {
+        let native = native_io_priority(io_priority);
+        for tid in self.thread_ids() {
+            if unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, tid as libc::c_int, native) } != 0 {
+                let err = io::Error::last_os_error();
+                if err.raw_os_error() != Some(libc::ESRCH) {
+                    return Err(errno_error(err, "set I/O priority", self.pid));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// How much more (positive) or less (negative) likely the OOM killer is to pick this process, -1000 to 1000
+    pub fn oom_score_adj(&self) -> Result<i32, ManagerError> {
+        fs::read_to_string(format!("/proc/{}/oom_score_adj", self.pid))
+            .map_err(|err| errno_error(err, "get OOM score adjustment", self.pid))?
+            .trim()
+            .parse()
+            .map_err(|err| ManagerError::other(format!("Failed to parse OOM score adjustment for PID {}: {}", self.pid, err)))
+    }
+
+    pub fn set_oom_score_adj(&self, oom_score_adj: i32) -> Result<(), ManagerError> {
+        fs::write(format!("/proc/{}/oom_score_adj", self.pid), oom_score_adj.to_string()).map_err(|err| {
+            if err.kind() == io::ErrorKind::PermissionDenied {
+                ManagerError::access_denied(
+                    self.pid,
+                    format!("Access denied to set OOM score adjustment for PID {}. Lowering it needs root or CAP_SYS_RESOURCE.", self.pid),
+                )
+            } else {
+                errno_error(err, "set OOM score adjustment", self.pid)
+            }
+        })
+    }
+
+    /// Memory policies and resident pages per node, summed over /proc/<pid>/numa_maps
+    pub fn numa_usage(&self) -> Result<NumaUsage, ManagerError> {
+        let numa_maps = fs::read_to_string(format!("/proc/{}/numa_maps", self.pid))
+            .map_err(|err| errno_error(err, "read NUMA placement", self.pid))?;
+
+        let mut usage = NumaUsage::default();
+        for line in numa_maps.lines() {
+            let mut fields = line.split_whitespace().skip(1);
+            if let Some(policy) = fields.next() {
+                if !usage.policies.iter().any(|known| known == policy) {
+                    usage.policies.push(policy.to_string());
+                }
+            }
+
+            // Per-node page counts look like "N1=42"
+            for (node, pages) in fields.filter_map(|field| field.strip_prefix('N')?.split_once('=')) {
+                if let (Ok(node), Ok(pages)) = (node.parse::<u32>(), pages.parse::<u64>()) {
+                    *usage.pages_per_node.entry(node).or_insert(0) += pages;
+                }
+            }
+        }
+        Ok(usage)
+    }
+
+    /// Move the process's pages from one set of nodes to another, returning how many stayed behind.
+    /// Linux can't change another process's memory policy, so placement is enforced this way.
+    pub fn migrate_pages(&self, from_nodes: u64, to_nodes: u64) -> Result<u64, ManagerError> {
+        // maxnode counts one past the last bit the kernel reads from each mask
+        let maxnode: libc::c_ulong = 65;
+        let result = unsafe {
+            libc::syscall(libc::SYS_migrate_pages, self.pid as libc::pid_t, maxnode, &from_nodes as *const u64, &to_nodes as *const u64)
+        };
+        if result < 0 {
+            return Err(errno_error(io::Error::last_os_error(), "migrate memory pages", self.pid));
+        }
+        Ok(result as u64)
+    }
+
+    /// Total user + system CPU time consumed by the process
+    pub fn cpu_time(&self) -> Option<Duration> {
+        let stat = fs::read_to_string(format!("/proc/{}/stat", self.pid)).ok()?;
+        // Fields after the parenthesised command name; utime and stime are fields 14 and 15
+        let fields: Vec<&str> = stat.get(stat.rfind(')')? + 2..)?.split_whitespace().collect();
+        let utime: u64 = fields.get(11)?.parse().ok()?;
+        let stime: u64 = fields.get(12)?.parse().ok()?;
+
+        let ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+        if ticks_per_second <= 0 {
+            return None;
+        }
+        Some(Duration::from_secs_f64((utime + stime) as f64 / ticks_per_second as f64))
+    }
+
+    /// When the process started, as time since boot; only meaningful for ordering processes
+    pub fn start_time(&self) -> Option<Duration> {
+        let stat = fs::read_to_string(format!("/proc/{}/stat", self.pid)).ok()?;
+        // starttime is field 22, in clock ticks since boot
+        let fields: Vec<&str> = stat.get(stat.rfind(')')? + 2..)?.split_whitespace().collect();
+        let start_ticks: u64 = fields.get(19)?.parse().ok()?;
+
+        let ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+        if ticks_per_second <= 0 {
+            return None;
+        }
+        Some(Duration::from_secs_f64(start_ticks as f64 / ticks_per_second as f64))
+    }
+
+    /// Identity of the user owning the process (its UID), as raw bytes for comparison
+    pub fn owner_id(&self) -> Option<Vec<u8>> {
+        let metadata = fs::metadata(format!("/proc/{}", self.pid)).ok()?;
+        Some(metadata.uid().to_ne_bytes().to_vec())
+    }
+
+    /// Freeze the process with SIGSTOP
+    pub fn suspend(&self) -> Result<(), ManagerError> {
+        self.signal(libc::SIGSTOP, "suspend process")
+    }
+
+    /// Thaw a process frozen by `suspend`
+    pub fn resume(&self) -> Result<(), ManagerError> {
+        self.signal(libc::SIGCONT, "resume process")
+    }
+
+    fn signal(&self, signal: libc::c_int, action: &str) -> Result<(), ManagerError> {
+        if unsafe { libc::kill(self.pid as libc::pid_t, signal) } == 0 {
+            Ok(())
+        } else {
+            Err(errno_error(io::Error::last_os_error(), action, self.pid))
+        }
+    }
+
+    pub fn terminate(&self) -> Result<(), ManagerError> {
+        if unsafe { libc::kill(self.pid as libc::pid_t, libc::SIGKILL) } == 0 {
+            Ok(())
+        } else {
+            Err(errno_error(io::Error::last_os_error(), "terminate process", self.pid))
+        }
+    }
+
+    /// Ask the process to exit with SIGTERM, leaving it a chance to clean up
+    pub fn request_close(&self) -> Result<(), ManagerError> {
+        self.signal(libc::SIGTERM, "request process exit")
+    }
+
+    /// Wait up to `timeout` for the process to exit, returning whether it did
+    pub fn wait_for_exit(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            // A zombie has exited and only waits for its parent to reap it
+            let exited = fs::read_to_string(format!("/proc/{}/stat", self.pid))
+                .map(|stat| stat.rsplit_once(')').and_then(|(_, rest)| rest.split_whitespace().next()) == Some("Z"))
+                .unwrap_or(true);
+
+            if exited {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+}
+
+fn get_thread_affinity(tid: u32) -> io::Result<u64> {
+    unsafe {
+        let mut cpu_set: libc::cpu_set_t = mem::zeroed();
+        if libc::sched_getaffinity(tid as libc::pid_t, mem::size_of::<libc::cpu_set_t>(), &mut cpu_set) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok((0..64).filter(|&cpu| libc::CPU_ISSET(cpu, &cpu_set)).fold(0u64, |acc, cpu| acc | (1u64 << cpu)))
+    }
+}
+
+fn set_thread_affinity(tid: u32, affinity_mask: u64) -> io::Result<()> {
+    unsafe {
+        let mut cpu_set: libc::cpu_set_t = mem::zeroed();
+        for cpu in (0..64).filter(|cpu| affinity_mask & (1u64 << cpu) != 0) {
+            libc::CPU_SET(cpu, &mut cpu_set);
+        }
+
+        if libc::sched_setaffinity(tid as libc::pid_t, mem::size_of::<libc::cpu_set_t>(), &cpu_set) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+fn get_thread_nice(tid: u32) -> io::Result<i32> {
+    unsafe {
+        // -1 is a valid nice value, so errors are only detectable through errno
+        *libc::__errno_location() = 0;
+        let nice = libc::getpriority(libc::PRIO_PROCESS, tid as libc::id_t);
+        if nice == -1 && *libc::__errno_location() != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(nice)
+    }
+}
+
+fn set_thread_nice(tid: u32, nice: i32) -> io::Result<()> {
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, tid as libc::id_t, nice) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_interrupt(_signal: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Catch Ctrl+C and termination requests so a foreground loop can stop cleanly
+pub fn catch_interrupts() {
+    unsafe {
+        libc::signal(libc::SIGINT, on_interrupt as *const () as libc::sighandler_t);
+        libc::signal(libc::SIGTERM, on_interrupt as *const () as libc::sighandler_t);
+    }
+}
+
+/// Whether an interrupt arrived since `catch_interrupts`
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Identity of the user running this application, comparable with `ProcessHandle::owner_id`
+pub fn current_user_id() -> Option<Vec<u8>> {
+    Some(unsafe { libc::getuid() }.to_ne_bytes().to_vec())
+}
+
+/// Executable name of a process: the binary's file name, falling back to its command name
+pub fn process_name(pid: u32) -> Option<String> {
+    let from_exe = fs::read_link(format!("/proc/{}/exe", pid))
+        .ok()
+        .and_then(|path| path.file_name().map(|name| name.to_string_lossy().into_owned()));
+
+    let from_cmdline = || {
+        let cmdline = fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+        let argv0 = cmdline.split(|&b| b == 0).next().filter(|arg| !arg.is_empty())?;
+        let argv0 = String::from_utf8_lossy(argv0);
+        Path::new(argv0.as_ref()).file_name().map(|name| name.to_string_lossy().into_owned())
+    };
+
+    let from_comm = || {
+        fs::read_to_string(format!("/proc/{}/comm", pid))
+            .ok()
+            .map(|comm| comm.trim_end().to_string())
+    };
+
+    from_exe.or_else(from_cmdline).or_else(from_comm)
+}
+
+/// Take a snapshot of every running process as (PID, executable name) pairs
+pub fn snapshot_processes() -> Result<Vec<(u32, String)>, ManagerError> {
+    let entries = fs::read_dir("/proc").map_err(|err| ManagerError::os(&err, "Failed to read /proc"))?;
+
+    Ok(entries
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(|pid| Some((pid, process_name(pid)?)))
+        .collect())
+}
+
+/// Parent PID of every running process
+pub fn parent_pids() -> HashMap<u32, u32> {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return HashMap::new();
+    };
+
+    entries
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(|pid| {
+            let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+            // The parent PID is the second field after the parenthesised command name
+            let ppid = stat.get(stat.rfind(')')? + 2..)?.split_whitespace().nth(1)?.parse().ok()?;
+            Some((pid, ppid))
+        })
+        .collect()
+}
+
+/// Init and kernel threads, which no rule may ever touch
+pub fn is_system_process(pid: u32) -> bool {
+    // Kernel threads are the only processes without a command line
+    pid <= 2
+        || fs::read(format!("/proc/{}/cmdline", pid))
+            .map(|cmdline| cmdline.is_empty())
+            .unwrap_or(false)
+}
+
+pub fn is_elevated() -> bool {
+    unsafe { libc::geteuid() == 0 }
+}
+
+pub fn cpu_count() -> u32 {
+    let count = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_ONLN) };
+    if count > 0 { count as u32 } else { 1 }
+}
+
+/// NUMA nodes with the mask of CPUs each one holds; empty when the kernel exposes no topology
+pub fn numa_topology() -> Vec<(u32, u64)> {
+    let Ok(entries) = fs::read_dir("/sys/devices/system/node") else {
+        return Vec::new();
+    };
+
+    let nodes: BTreeMap<u32, u64> = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let node: u32 = entry.file_name().to_str()?.strip_prefix("node")?.parse().ok()?;
+            let cpu_list = fs::read_to_string(entry.path().join("cpulist")).ok()?;
+            (node < 64).then(|| (node, parse_cpu_list(cpu_list.trim())))
+        })
+        .collect();
+    nodes.into_iter().collect()
+}
+
+/// Parse a kernel CPU list such as "0-3,8" into a mask
+fn parse_cpu_list(cpu_list: &str) -> u64 {
+    cpu_list
+        .split(',')
+        .filter_map(|range| {
+            let (start, end) = range.split_once('-').unwrap_or((range, range));
+            Some(start.trim().parse::<u32>().ok()?..=end.trim().parse::<u32>().ok()?)
+        })
+        .flatten()
+        .filter(|cpu| *cpu < 64)
+        .fold(0u64, |acc, cpu| acc | (1u64 << cpu))
+}
+
+/// The set of CPUs this application may run on, or `None` if it can't be queried
+pub fn system_affinity_mask() -> Option<u64> {
+    get_thread_affinity(0).ok()
+}
@@ -0,0 +1,12 @@
+// OS backends: each exposes the same free functions and a `ProcessHandle` type
+// that the process manager uses to read and change process settings.
+
+#[cfg(windows)]
+mod windows;
+#[cfg(windows)]
+pub use self::windows::*;
+
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "linux")]
+pub use self::linux::*;
@@ -0,0 +1,554 @@
+use std::collections::HashMap;
+use std::io;
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use winapi::shared::minwindef::{BOOL, DWORD, FALSE, FILETIME, LPARAM, TRUE};
+use winapi::shared::windef::HWND;
+use winapi::shared::ntdef::{HANDLE, NULL};
+use winapi::um::consoleapi::SetConsoleCtrlHandler;
+use winapi::um::handleapi::{CloseHandle, INVALID_HANDLE_VALUE};
+use winapi::um::processthreadsapi::{
+    GetCurrentProcess, GetPriorityClass, GetProcessId, GetProcessTimes, OpenProcess,
+    OpenProcessToken, OpenThread, ResumeThread, SetPriorityClass, SuspendThread, TerminateProcess,
+};
+use winapi::um::securitybaseapi::{GetLengthSid, GetTokenInformation};
+use winapi::um::synchapi::WaitForSingleObject;
+use winapi::um::sysinfoapi::{GetSystemInfo, SYSTEM_INFO};
+use winapi::um::tlhelp32::{
+    CreateToolhelp32Snapshot, Process32First, Process32Next, Thread32First, Thread32Next,
+    PROCESSENTRY32, TH32CS_SNAPPROCESS, TH32CS_SNAPTHREAD, THREADENTRY32,
+};
+use winapi::um::winbase::{
+    GetProcessAffinityMask, SetProcessAffinityMask, ABOVE_NORMAL_PRIORITY_CLASS,
+    BELOW_NORMAL_PRIORITY_CLASS, HIGH_PRIORITY_CLASS, IDLE_PRIORITY_CLASS, NORMAL_PRIORITY_CLASS,
+    REALTIME_PRIORITY_CLASS, WAIT_OBJECT_0,
+};
+use winapi::um::winuser::{EnumWindows, GetWindowThreadProcessId, IsWindowVisible, PostMessageW, WM_CLOSE};
+use winapi::um::winnt::{
+    TokenElevation, TokenUser, PROCESS_QUERY_INFORMATION, PROCESS_QUERY_LIMITED_INFORMATION,
+    PROCESS_SET_INFORMATION, PROCESS_SUSPEND_RESUME, PROCESS_TERMINATE, SYNCHRONIZE, THREAD_SUSPEND_RESUME,
+    TOKEN_ELEVATION, TOKEN_QUERY, TOKEN_USER,
+};
+
+use crate::error::ManagerError;
+use crate::numa::NumaUsage;
+use crate::priority::{IoPriority, Priority, Scheduling};
+
+pub const SUPPORTS_SCHEDULING_POLICY: bool = false;
+pub const SUPPORTS_IO_PRIORITY: bool = false;
+pub const SUPPORTS_CGROUPS: bool = false;
+pub const SUPPORTS_OOM_SCORE_ADJ: bool = false;
+pub const SUPPORTS_NUMA: bool = false;
+pub const SUPPORTS_THREAD_RULES: bool = false;
+
+/// Map a priority to its Windows priority class
+pub fn priority_class(priority: Priority) -> u32 {
+    match priority {
+        Priority::Idle => IDLE_PRIORITY_CLASS,
+        Priority::BelowNormal => BELOW_NORMAL_PRIORITY_CLASS,
+        Priority::Normal => NORMAL_PRIORITY_CLASS,
+        Priority::AboveNormal => ABOVE_NORMAL_PRIORITY_CLASS,
+        Priority::High => HIGH_PRIORITY_CLASS,
+        Priority::Realtime => REALTIME_PRIORITY_CLASS,
+    }
+}
+
+/// The error left by a failed Windows call, with its code
+fn last_os_error(message: &str) -> ManagerError {
+    let err = io::Error::last_os_error();
+    match err.raw_os_error() {
+        // ERROR_ACCESS_DENIED
+        Some(5) => ManagerError::access_denied(None, format!("{}: access denied. Run as Administrator.", message)),
+        _ => ManagerError::os(&err, message),
+    }
+}
+
+/// An open process handle, closed when dropped
+pub struct ProcessHandle {
+    handle: HANDLE,
+}
+
+// A process handle may be used and closed from any thread
+unsafe impl Send for ProcessHandle {}
+
+impl ProcessHandle {
+    unsafe fn open(pid: u32, access: DWORD) -> Option<Self> {
+        let handle = OpenProcess(access, FALSE, pid);
+        if handle == NULL { None } else { Some(Self { handle }) }
+    }
+
+    /// Open a process for reading and changing its scheduling settings, without diagnostics
+    pub fn try_open_for_update(pid: u32) -> Option<Self> {
+        unsafe { Self::open(pid, PROCESS_QUERY_INFORMATION | PROCESS_SET_INFORMATION) }
+    }
+
+    /// Open a process with the rights needed to read and change its scheduling settings
+    pub fn open_for_update(pid: u32) -> Result<Self, ManagerError> {
+        // Try with full permissions first
+        if let Some(process) = Self::try_open_for_update(pid) {
+            return Ok(process);
+        }
+
+        // If that fails, try with limited permissions to tell "exists" from "gone"
+        if Self::open_for_query(pid).is_none() {
+            let process_info = "Process may have exited or is a protected system process";
+
+            if is_elevated() {
+                Err(ManagerError::process_exited(pid, format!("Failed to open process PID {}: {}. Process may be protected or have exited.", pid, process_info)))
+            } else {
+                Err(ManagerError::access_denied(pid, format!("Access denied to PID {}: {}. Right-click the application and 'Run as Administrator'.", pid, process_info)))
+            }
+        } else {
+            Err(ManagerError::access_denied(pid, format!("Insufficient permissions for PID {}. Can read process but cannot modify settings. Run as Administrator.", pid)))
+        }
+    }
+
+    /// Open a process for reading its settings only
+    pub fn open_for_query(pid: u32) -> Option<Self> {
+        unsafe {
+            Self::open(pid, PROCESS_QUERY_INFORMATION)
+                .or_else(|| Self::open(pid, PROCESS_QUERY_LIMITED_INFORMATION))
+        }
+    }
+
+    /// Open a process so it can be suspended and resumed
+    pub fn open_for_suspend(pid: u32) -> Result<Self, ManagerError> {
+        unsafe { Self::open(pid, PROCESS_SUSPEND_RESUME | PROCESS_QUERY_LIMITED_INFORMATION) }.ok_or_else(|| {
+            if is_elevated() {
+                ManagerError::process_exited(pid, format!("Failed to open process PID {} for suspension. Process may have already exited or is protected.", pid))
+            } else {
+                ManagerError::access_denied(pid, format!("Access denied to suspend PID {}. Run as Administrator to suspend processes.", pid))
+            }
+        })
+    }
+
+    /// Open a process so it can be terminated and waited on
+    pub fn open_for_terminate(pid: u32) -> Result<Self, ManagerError> {
+        unsafe { Self::open(pid, PROCESS_TERMINATE | SYNCHRONIZE | PROCESS_QUERY_LIMITED_INFORMATION) }.ok_or_else(|| {
+            if is_elevated() {
+                ManagerError::process_exited(pid, format!("Failed to open process PID {} for termination. Process may have already exited or is protected.", pid))
+            } else {
+                ManagerError::access_denied(pid, format!("Access denied to terminate PID {}. Run as Administrator to terminate processes.", pid))
+            }
+        })
+    }
+
+    /// Thread rules are Linux-only; no threads are reported
+    pub fn threads(&self) -> Vec<(u32, String)> {
+        Vec::new()
+    }
+
+    pub fn thread_affinity(&self, _tid: u32) -> Result<u64, ManagerError> {
+        Err(ManagerError::backend_unavailable("Thread rules are not supported on Windows"))
+    }
+
+    pub fn set_thread_affinity(&self, _tid: u32, _affinity_mask: u64) -> Result<(), ManagerError> {
+        Err(ManagerError::backend_unavailable("Thread rules are not supported on Windows"))
+    }
+
+    pub fn thread_priority(&self, _tid: u32) -> Result<Priority, ManagerError> {
+        Err(ManagerError::backend_unavailable("Thread rules are not supported on Windows"))
+    }
+
+    pub fn set_thread_priority(&self, _tid: u32, _priority: Priority) -> Result<(), ManagerError> {
+        Err(ManagerError::backend_unavailable("Thread rules are not supported on Windows"))
+    }
+
+    pub fn affinity(&self) -> Result<u64, ManagerError> {
+        let mut process_affinity: usize = 0;
+        let mut system_affinity: usize = 0;
+
+        if unsafe { GetProcessAffinityMask(self.handle, &mut process_affinity, &mut system_affinity) } != 0 {
+            Ok(process_affinity as u64)
+        } else {
+            Err(last_os_error("Failed to get current process affinity"))
+        }
+    }
+
+    /// Thread rules aren't supported here, so no thread is ever claimed
+    pub fn set_affinity(&self, affinity_mask: u64, _claimed_tids: &[u32]) -> Result<(), ManagerError> {
+        if unsafe { SetProcessAffinityMask(self.handle, affinity_mask as u32) } != 0 {
+            Ok(())
+        } else {
+            Err(last_os_error("Failed to set process affinity"))
+        }
+    }
+
+    pub fn priority(&self) -> Result<Priority, ManagerError> {
+        let priority_class = unsafe { GetPriorityClass(self.handle) };
+        if priority_class == 0 {
+            return Err(last_os_error("Failed to get current process priority"));
+        }
+
+        Priority::from_legacy_value(priority_class)
+            .ok_or_else(|| ManagerError::other(format!("Unknown priority class 0x{:X}", priority_class)))
+    }
+
+    pub fn set_priority(&self, priority: Priority, _claimed_tids: &[u32]) -> Result<(), ManagerError> {
+        if unsafe { SetPriorityClass(self.handle, priority_class(priority)) } != 0 {
+            Ok(())
+        } else {
+            Err(last_os_error("Failed to set process priority"))
+        }
+    }
+
+    pub fn scheduling(&self) -> Result<Scheduling, ManagerError> {
+        Err(ManagerError::backend_unavailable("Scheduling policies are not supported on Windows"))
+    }
+
+    pub fn set_scheduling(&self, _scheduling: Scheduling) -> Result<(), ManagerError> {
+        Err(ManagerError::backend_unavailable("Scheduling policies are not supported on Windows"))
+    }
+
+    pub fn io_priority(&self) -> Result<IoPriority, ManagerError> {
+        Err(ManagerError::backend_unavailable("I/O priorities are not supported on Windows"))
+    }
+
+    pub fn set_io_priority(&self, _io_priority: IoPriority) -> Result<(), ManagerError> {
+        Err(ManagerError::backend_unavailable("I/O priorities are not supported on Windows"))
+    }
+
+    pub fn oom_score_adj(&self) -> Result<i32, ManagerError> {
+        Err(ManagerError::backend_unavailable("OOM score adjustment is not supported on Windows"))
+    }
+
+    pub fn set_oom_score_adj(&self, _oom_score_adj: i32) -> Result<(), ManagerError> {
+        Err(ManagerError::backend_unavailable("OOM score adjustment is not supported on Windows"))
+    }
+
+    pub fn numa_usage(&self) -> Result<NumaUsage, ManagerError> {
+        Err(ManagerError::backend_unavailable("NUMA placement is not supported on Windows"))
+    }
+
+    pub fn migrate_pages(&self, _from_nodes: u64, _to_nodes: u64) -> Result<u64, ManagerError> {
+        Err(ManagerError::backend_unavailable("NUMA placement is not supported on Windows"))
+    }
+
+    /// Total kernel + user CPU time consumed by the process
+    pub fn cpu_time(&self) -> Option<Duration> {
+        unsafe {
+            let mut creation_time: FILETIME = mem::zeroed();
+            let mut exit_time: FILETIME = mem::zeroed();
+            let mut kernel_time: FILETIME = mem::zeroed();
+            let mut user_time: FILETIME = mem::zeroed();
+
+            let result = GetProcessTimes(
+                self.handle,
+                &mut creation_time,
+                &mut exit_time,
+                &mut kernel_time,
+                &mut user_time,
+            );
+
+            if result == 0 {
+                return None;
+            }
+
+            // FILETIME counts 100ns intervals
+            let to_u64 = |time: FILETIME| ((time.dwHighDateTime as u64) << 32) | time.dwLowDateTime as u64;
+            Some(Duration::from_nanos((to_u64(kernel_time) + to_u64(user_time)) * 100))
+        }
+    }
+
+    /// When the process started, as time since 1601; only meaningful for ordering processes
+    pub fn start_time(&self) -> Option<Duration> {
+        unsafe {
+            let mut creation_time: FILETIME = mem::zeroed();
+            let mut exit_time: FILETIME = mem::zeroed();
+            let mut kernel_time: FILETIME = mem::zeroed();
+            let mut user_time: FILETIME = mem::zeroed();
+
+            if GetProcessTimes(self.handle, &mut creation_time, &mut exit_time, &mut kernel_time, &mut user_time) == 0 {
+                return None;
+            }
+
+            let ticks = ((creation_time.dwHighDateTime as u64) << 32) | creation_time.dwLowDateTime as u64;
+            Some(Duration::from_nanos(ticks * 100))
+        }
+    }
+
+    /// Identity of the user owning the process (its SID), as raw bytes for comparison
+    pub fn owner_id(&self) -> Option<Vec<u8>> {
+        unsafe { query_token_user_sid(self.handle) }
+    }
+
+    /// Freeze every thread of the process
+    pub fn suspend(&self) -> Result<(), ManagerError> {
+        // SuspendThread returns the previous suspend count, or -1 on failure
+        unsafe { self.for_each_thread(|thread| SuspendThread(thread) != DWORD::MAX) }
+            .map_err(|_| last_os_error("Failed to suspend process"))
+    }
+
+    /// Thaw a process frozen by `suspend`
+    pub fn resume(&self) -> Result<(), ManagerError> {
+        unsafe { self.for_each_thread(|thread| ResumeThread(thread) != DWORD::MAX) }
+            .map_err(|_| last_os_error("Failed to resume process"))
+    }
+
+    /// Run an operation on every thread of the process; Windows has no documented process-wide suspend
+    unsafe fn for_each_thread(&self, operation: impl Fn(HANDLE) -> bool) -> Result<(), ()> {
+        let pid = GetProcessId(self.handle);
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0);
+        if pid == 0 || snapshot == INVALID_HANDLE_VALUE {
+            return Err(());
+        }
+
+        let mut thread_entry: THREADENTRY32 = mem::zeroed();
+        thread_entry.dwSize = mem::size_of::<THREADENTRY32>() as DWORD;
+
+        let mut all_succeeded = true;
+        if Thread32First(snapshot, &mut thread_entry) != FALSE {
+            loop {
+                if thread_entry.th32OwnerProcessID == pid {
+                    let thread = OpenThread(THREAD_SUSPEND_RESUME, FALSE, thread_entry.th32ThreadID);
+                    if thread == NULL {
+                        all_succeeded = false;
+                    } else {
+                        all_succeeded &= operation(thread);
+                        CloseHandle(thread);
+                    }
+                }
+
+                if Thread32Next(snapshot, &mut thread_entry) == FALSE {
+                    break;
+                }
+            }
+        }
+
+        CloseHandle(snapshot);
+        if all_succeeded { Ok(()) } else { Err(()) }
+    }
+
+    pub fn terminate(&self) -> Result<(), ManagerError> {
+        if unsafe { TerminateProcess(self.handle, 1) } != 0 {
+            Ok(())
+        } else {
+            Err(last_os_error("Failed to terminate process"))
+        }
+    }
+
+    /// Ask the process to close by sending WM_CLOSE to its visible top-level windows
+    pub fn request_close(&self) -> Result<(), ManagerError> {
+        unsafe extern "system" fn post_close(window: HWND, lparam: LPARAM) -> BOOL {
+            let target = &mut *(lparam as *mut (DWORD, bool));
+            let mut owner: DWORD = 0;
+            GetWindowThreadProcessId(window, &mut owner);
+            if owner == target.0 && IsWindowVisible(window) != FALSE && PostMessageW(window, WM_CLOSE, 0, 0) != FALSE {
+                target.1 = true;
+            }
+            TRUE
+        }
+
+        let mut target = (unsafe { GetProcessId(self.handle) }, false);
+        unsafe { EnumWindows(Some(post_close), &mut target as *mut (DWORD, bool) as LPARAM) };
+
+        if target.1 {
+            Ok(())
+        } else {
+            // Console and background processes have no window to close
+            Err(ManagerError::other("Process has no window to close"))
+        }
+    }
+
+    /// Wait up to `timeout` for the process to exit, returning whether it did
+    pub fn wait_for_exit(&self, timeout: Duration) -> bool {
+        unsafe { WaitForSingleObject(self.handle, timeout.as_millis() as DWORD) == WAIT_OBJECT_0 }
+    }
+}
+
+impl Drop for ProcessHandle {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.handle);
+        }
+    }
+}
+
+unsafe fn query_token_user_sid(process_handle: HANDLE) -> Option<Vec<u8>> {
+    let mut token_handle = NULL;
+    if OpenProcessToken(process_handle, TOKEN_QUERY, &mut token_handle) == 0 {
+        return None;
+    }
+
+    // u64 storage keeps the TOKEN_USER structure suitably aligned
+    let mut buffer = [0u64; 64];
+    let mut return_length = 0u32;
+
+    let result = GetTokenInformation(
+        token_handle,
+        TokenUser,
+        buffer.as_mut_ptr() as *mut _,
+        mem::size_of_val(&buffer) as u32,
+        &mut return_length,
+    );
+
+    CloseHandle(token_handle);
+
+    if result == 0 {
+        return None;
+    }
+
+    let token_user = &*(buffer.as_ptr() as *const TOKEN_USER);
+    let sid = token_user.User.Sid;
+    let sid_length = GetLengthSid(sid) as usize;
+    Some(std::slice::from_raw_parts(sid as *const u8, sid_length).to_vec())
+}
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+unsafe extern "system" fn on_interrupt(_ctrl_type: DWORD) -> BOOL {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+    TRUE
+}
+
+/// Catch Ctrl+C and console close events so a foreground loop can stop cleanly
+pub fn catch_interrupts() {
+    unsafe {
+        SetConsoleCtrlHandler(Some(on_interrupt), TRUE);
+    }
+}
+
+/// Whether an interrupt arrived since `catch_interrupts`
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Identity of the user running this application, comparable with `ProcessHandle::owner_id`
+pub fn current_user_id() -> Option<Vec<u8>> {
+    unsafe { query_token_user_sid(GetCurrentProcess()) }
+}
+
+/// Take a snapshot of every running process as (PID, executable name) pairs
+pub fn snapshot_processes() -> Result<Vec<(u32, String)>, ManagerError> {
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0);
+        if snapshot == INVALID_HANDLE_VALUE {
+            return Err(last_os_error("Failed to create process snapshot"));
+        }
+
+        let mut process_entry: PROCESSENTRY32 = mem::zeroed();
+        process_entry.dwSize = mem::size_of::<PROCESSENTRY32>() as DWORD;
+
+        if Process32First(snapshot, &mut process_entry) == FALSE {
+            CloseHandle(snapshot);
+            return Err(last_os_error("Failed to get first process"));
+        }
+
+        let mut processes = Vec::new();
+
+        loop {
+            processes.push((process_entry.th32ProcessID, exe_name_from_entry(&process_entry)));
+
+            if Process32Next(snapshot, &mut process_entry) == FALSE {
+                break;
+            }
+        }
+
+        CloseHandle(snapshot);
+        Ok(processes)
+    }
+}
+
+/// Parent PID of every running process
+pub fn parent_pids() -> HashMap<u32, u32> {
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0);
+        if snapshot == INVALID_HANDLE_VALUE {
+            return HashMap::new();
+        }
+
+        let mut process_entry: PROCESSENTRY32 = mem::zeroed();
+        process_entry.dwSize = mem::size_of::<PROCESSENTRY32>() as DWORD;
+
+        let mut parents = HashMap::new();
+        if Process32First(snapshot, &mut process_entry) != FALSE {
+            loop {
+                parents.insert(process_entry.th32ProcessID, process_entry.th32ParentProcessID);
+
+                if Process32Next(snapshot, &mut process_entry) == FALSE {
+                    break;
+                }
+            }
+        }
+
+        CloseHandle(snapshot);
+        parents
+    }
+}
+
+fn exe_name_from_entry(entry: &PROCESSENTRY32) -> String {
+    let exe_file = &entry.szExeFile;
+    let len = exe_file.iter().position(|&c| c == 0).unwrap_or(exe_file.len());
+    let bytes: Vec<u8> = exe_file[..len].iter().map(|&c| c as u8).collect();
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+/// Kernel pseudo-processes that no rule may ever touch
+pub fn is_system_process(pid: u32) -> bool {
+    // PID 0 (System Idle Process) and PID 4 (System)
+    pid == 0 || pid == 4
+}
+
+pub fn is_elevated() -> bool {
+    unsafe {
+        let current_process = GetCurrentProcess();
+        let mut token_handle = NULL;
+
+        if OpenProcessToken(current_process, TOKEN_QUERY, &mut token_handle) == 0 {
+            return false;
+        }
+
+        let mut elevation = TOKEN_ELEVATION { TokenIsElevated: 0 };
+        let mut return_length = 0u32;
+
+        let result = GetTokenInformation(
+            token_handle,
+            TokenElevation,
+            &mut elevation as *mut _ as *mut _,
+            mem::size_of::<TOKEN_ELEVATION>() as u32,
+            &mut return_length,
+        );
+
+        CloseHandle(token_handle);
+
+        result != 0 && elevation.TokenIsElevated != 0
+    }
+}
+
+pub fn cpu_count() -> u32 {
+    unsafe {
+        let mut system_info: SYSTEM_INFO = mem::zeroed();
+        GetSystemInfo(&mut system_info);
+        system_info.dwNumberOfProcessors
+    }
+}
+
+/// NUMA nodes with the mask of CPUs each one holds; NUMA placement is Linux-only
+pub fn numa_topology() -> Vec<(u32, u64)> {
+    Vec::new()
+}
+
+/// The system's processor affinity mask, or `None` if it can't be queried
+pub fn system_affinity_mask() -> Option<u64> {
+    unsafe {
+        let current_process = GetCurrentProcess();
+        let mut process_affinity: usize = 0;
+        let mut system_affinity: usize = 0;
+
+        if GetProcessAffinityMask(current_process, &mut process_affinity, &mut system_affinity) != 0 {
+            Some(system_affinity as u64)
+        } else {
+            None
+        }
+    }
+}
+
+/// Executable name of a process
+pub fn process_name(pid: u32) -> Option<String> {
+    snapshot_processes()
+        .ok()?
+        .into_iter()
+        .find(|(process_pid, _)| *process_pid == pid)
+        .map(|(_, name)| name)
+}
@@ -0,0 +1,287 @@
+use std::fmt;
+use std::str::FromStr;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Platform-independent process priority; each backend maps it to native values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum Priority {
+    Idle,
+    BelowNormal,
+    Normal,
+    AboveNormal,
+    High,
+    Realtime,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 6] = [
+        Priority::Idle,
+        Priority::BelowNormal,
+        Priority::Normal,
+        Priority::AboveNormal,
+        Priority::High,
+        Priority::Realtime,
+    ];
+
+    /// Map a Windows priority class constant, as stored by older configs, to a priority
+    pub fn from_legacy_value(value: u32) -> Option<Self> {
+        match value {
+            0x00000040 => Some(Priority::Idle),
+            0x00004000 => Some(Priority::BelowNormal),
+            0x00000020 => Some(Priority::Normal),
+            0x00008000 => Some(Priority::AboveNormal),
+            0x00000080 => Some(Priority::High),
+            0x00000100 => Some(Priority::Realtime),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Priority::Idle => "Idle",
+            Priority::BelowNormal => "BelowNormal",
+            Priority::Normal => "Normal",
+            Priority::AboveNormal => "AboveNormal",
+            Priority::High => "High",
+            Priority::Realtime => "Realtime",
+        }
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Priority {
+    type Err = String;
+
+    /// Accepts "AboveNormal", "above_normal", "ABOVE_NORMAL" and "above-normal" alike
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized: String = value
+            .chars()
+            .filter(|c| *c != '_' && *c != '-' && *c != ' ')
+            .collect::<String>()
+            .to_lowercase();
+
+        Priority::ALL
+            .into_iter()
+            .find(|priority| priority.name().to_lowercase() == normalized)
+            .ok_or_else(|| format!("unknown priority '{}'", value))
+    }
+}
+
+struct PriorityVisitor;
+
+impl Visitor<'_> for PriorityVisitor {
+    type Value = Priority;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a priority name (Idle, BelowNormal, Normal, AboveNormal, High, Realtime) or a legacy priority class value")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Priority, E> {
+        value.parse().map_err(E::custom)
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Priority, E> {
+        u32::try_from(value)
+            .ok()
+            .and_then(Priority::from_legacy_value)
+            .ok_or_else(|| E::custom(format!("unknown priority class value 0x{:X}", value)))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Priority, E> {
+        u64::try_from(value)
+            .map_err(|_| E::custom(format!("unknown priority class value {}", value)))
+            .and_then(|value| self.visit_u64(value))
+    }
+}
+
+impl<'de> Deserialize<'de> for Priority {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(PriorityVisitor)
+    }
+}
+
+/// Linux scheduling policy applied alongside the nice-based priority
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SchedulingPolicy {
+    #[serde(alias = "SCHED_OTHER")]
+    Other,
+    #[serde(alias = "SCHED_BATCH")]
+    Batch,
+    #[serde(alias = "SCHED_IDLE")]
+    Idle,
+    #[serde(alias = "SCHED_FIFO")]
+    Fifo,
+    #[serde(alias = "SCHED_RR")]
+    RoundRobin,
+}
+
+impl SchedulingPolicy {
+    /// FIFO and round-robin are real-time policies and need an RT priority of 1-99
+    pub fn is_realtime(self) -> bool {
+        matches!(self, SchedulingPolicy::Fifo | SchedulingPolicy::RoundRobin)
+    }
+}
+
+/// A scheduling policy together with its real-time priority (0 for non-real-time policies)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Scheduling {
+    pub policy: SchedulingPolicy,
+    pub rt_priority: u8,
+}
+
+impl Scheduling {
+    pub fn new(policy: SchedulingPolicy, rt_priority: Option<u8>) -> Result<Self, String> {
+        match (policy.is_realtime(), rt_priority) {
+            (true, Some(rt_priority @ 1..=99)) => Ok(Self { policy, rt_priority }),
+            (true, Some(rt_priority)) => Err(format!("RT priority {} is out of range 1-99 for {:?}", rt_priority, policy)),
+            (true, None) => Err(format!("{:?} scheduling needs an RT priority of 1-99", policy)),
+            (false, None | Some(0)) => Ok(Self { policy, rt_priority: 0 }),
+            (false, Some(_)) => Err(format!("{:?} scheduling does not take an RT priority", policy)),
+        }
+    }
+}
+
+impl fmt::Display for Scheduling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.policy.is_realtime() {
+            write!(f, "{:?}({})", self.policy, self.rt_priority)
+        } else {
+            write!(f, "{:?}", self.policy)
+        }
+    }
+}
+
+/// Linux I/O scheduling class with its level, 0 (highest) to 7 (lowest), where the class takes one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "class", content = "level")]
+pub enum IoPriority {
+    #[serde(alias = "realtime", alias = "rt")]
+    Realtime(u8),
+    #[serde(alias = "best-effort", alias = "best_effort", alias = "be")]
+    BestEffort(u8),
+    #[serde(alias = "idle")]
+    Idle,
+}
+
+impl IoPriority {
+    pub const MAX_LEVEL: u8 = 7;
+
+    /// Reject levels outside 0-7
+    pub fn validate(self) -> Result<Self, String> {
+        match self {
+            IoPriority::Realtime(level) | IoPriority::BestEffort(level) if level > Self::MAX_LEVEL => {
+                Err(format!("I/O priority level {} is out of range 0-{}", level, Self::MAX_LEVEL))
+            }
+            _ => Ok(self),
+        }
+    }
+}
+
+impl fmt::Display for IoPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IoPriority::Realtime(level) => write!(f, "Realtime({})", level),
+            IoPriority::BestEffort(level) => write!(f, "BestEffort({})", level),
+            IoPriority::Idle => f.write_str("Idle"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Rule {
+        priority_class: Priority,
+    }
+
+    fn parse(toml: &str) -> Result<Priority, toml::de::Error> {
+        toml::from_str::<Rule>(toml).map(|rule| rule.priority_class)
+    }
+
+    #[test]
+    fn round_trips_every_priority_by_name() {
+        for priority in Priority::ALL {
+            let rule = Rule { priority_class: priority };
+            let saved = toml::to_string(&rule).unwrap();
+            assert_eq!(saved.trim(), format!("priority_class = \"{}\"", priority.name()));
+            assert_eq!(toml::from_str::<Rule>(&saved).unwrap(), rule);
+
+            let json = serde_json::to_string(&priority).unwrap();
+            assert_eq!(serde_json::from_str::<Priority>(&json).unwrap(), priority);
+        }
+    }
+
+    #[test]
+    fn accepts_other_spellings_of_names() {
+        assert_eq!(parse("priority_class = \"above_normal\"").unwrap(), Priority::AboveNormal);
+        assert_eq!(parse("priority_class = \"BELOW-NORMAL\"").unwrap(), Priority::BelowNormal);
+        assert_eq!(parse("priority_class = \"idle\"").unwrap(), Priority::Idle);
+    }
+
+    #[test]
+    fn accepts_legacy_priority_class_values() {
+        assert_eq!(parse("priority_class = 64").unwrap(), Priority::Idle);
+        assert_eq!(parse("priority_class = 16384").unwrap(), Priority::BelowNormal);
+        assert_eq!(parse("priority_class = 32").unwrap(), Priority::Normal);
+        assert_eq!(parse("priority_class = 32768").unwrap(), Priority::AboveNormal);
+        assert_eq!(parse("priority_class = 128").unwrap(), Priority::High);
+        assert_eq!(parse("priority_class = 256").unwrap(), Priority::Realtime);
+        assert_eq!(serde_json::from_str::<Priority>("32768").unwrap(), Priority::AboveNormal);
+    }
+
+    #[test]
+    fn rejects_unknown_priorities() {
+        assert!(parse("priority_class = \"Turbo\"").is_err());
+        assert!(parse("priority_class = 0").is_err());
+        assert!(parse("priority_class = -1").is_err());
+        assert!(parse("priority_class = 4294967360").is_err());
+    }
+
+    #[test]
+    fn scheduling_takes_an_rt_priority_only_for_realtime_policies() {
+        assert_eq!(Scheduling::new(SchedulingPolicy::Fifo, Some(50)).unwrap(), Scheduling { policy: SchedulingPolicy::Fifo, rt_priority: 50 });
+        assert!(Scheduling::new(SchedulingPolicy::RoundRobin, None).is_err());
+        assert!(Scheduling::new(SchedulingPolicy::Fifo, Some(0)).is_err());
+        assert!(Scheduling::new(SchedulingPolicy::Fifo, Some(100)).is_err());
+
+        assert_eq!(Scheduling::new(SchedulingPolicy::Batch, None).unwrap().rt_priority, 0);
+        assert!(Scheduling::new(SchedulingPolicy::Batch, Some(0)).is_ok());
+        assert!(Scheduling::new(SchedulingPolicy::Idle, Some(10)).is_err());
+
+        assert_eq!(Scheduling::new(SchedulingPolicy::RoundRobin, Some(10)).unwrap().to_string(), "RoundRobin(10)");
+        assert_eq!(Scheduling::new(SchedulingPolicy::Other, None).unwrap().to_string(), "Other");
+    }
+
+    #[test]
+    fn reads_scheduling_policies_by_their_kernel_names() {
+        assert_eq!(serde_json::from_str::<SchedulingPolicy>("\"SCHED_RR\"").unwrap(), SchedulingPolicy::RoundRobin);
+        assert_eq!(serde_json::from_str::<SchedulingPolicy>("\"Batch\"").unwrap(), SchedulingPolicy::Batch);
+    }
+
+    #[test]
+    fn io_priority_levels_stay_within_range() {
+        assert!(IoPriority::BestEffort(7).validate().is_ok());
+        assert!(IoPriority::Realtime(0).validate().is_ok());
+        assert!(IoPriority::BestEffort(8).validate().is_err());
+        assert!(IoPriority::Realtime(200).validate().is_err());
+        assert!(IoPriority::Idle.validate().is_ok());
+    }
+
+    #[test]
+    fn io_priority_is_a_class_with_a_level() {
+        let saved = serde_json::to_string(&IoPriority::BestEffort(4)).unwrap();
+        assert_eq!(saved, r#"{"class":"BestEffort","level":4}"#);
+        assert_eq!(serde_json::from_str::<IoPriority>(&saved).unwrap(), IoPriority::BestEffort(4));
+        assert_eq!(serde_json::from_str::<IoPriority>(r#"{"class":"be","level":2}"#).unwrap(), IoPriority::BestEffort(2));
+        assert_eq!(serde_json::from_str::<IoPriority>(r#"{"class":"idle"}"#).unwrap(), IoPriority::Idle);
+        assert_eq!(IoPriority::Realtime(1).to_string(), "Realtime(1)");
+    }
+}
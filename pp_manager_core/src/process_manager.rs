@@ -0,0 +1,2392 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Local};
+use serde::{Serialize, Deserialize};
+
+use crate::cgroup::{self, CgroupLimits, CgroupTree, MemoryLimits};
+use crate::error::ManagerError;
+use crate::events::EngineEvent;
+use crate::log_files;
+use crate::logs::{LogEntry, LogLevel};
+use crate::numa::{NumaPlacement, NumaUsage};
+use crate::platform::{self, ProcessHandle};
+use crate::priority::{IoPriority, Priority, Scheduling, SchedulingPolicy};
+
+// How long a forcibly terminated process gets to disappear
+const FORCED_EXIT_TIMEOUT: Duration = Duration::from_secs(2);
+
+// How long an extra instance gets to close before it is forcibly terminated
+const EXTRA_INSTANCE_CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Rule name the core reservation's changes are logged and emitted under
+pub const CORE_RESERVATION_RULE: &str = "Core reservation";
+
+// File every kill request is appended to, in the log directory
+const KILL_AUDIT_LOG: &str = "kill_audit.log";
+// Kill requests kept in memory for get_kill_audit; the file keeps them all
+const KILL_AUDIT_CAPACITY: usize = 1000;
+
+// Wait before retrying a process whose settings failed to apply, doubled per consecutive failure
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(60);
+
+// How often a process the manager lacks the rights for is tried again
+const UNMANAGEABLE_RETRY_INTERVAL: Duration = Duration::from_secs(300);
+
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProcessState {
+    NotFound,
+    Found(u32), // Single PID
+    FoundAndMonitoring(u32), // Single PID - process found and being continuously monitored
+    SettingsApplied(u32, String), // Single PID, details of what was changed
+    MultipleFound(Vec<u32>), // Multiple PIDs found
+    MultipleMonitoring(Vec<u32>), // Multiple PIDs being monitored
+    MultipleSettingsApplied(Vec<(u32, String)>), // Multiple PIDs with their respective changes
+
+    // New states for multi-process management
+    MultiProcessFound(HashMap<String, Vec<u32>>), // Process name -> PIDs
+    MultiProcessMonitoring(HashMap<String, Vec<u32>>), // Process name -> PIDs being monitored
+    MultiProcessSettingsApplied(HashMap<String, Vec<(u32, String)>>), // Process name -> (PID, changes)
+    Error(String),
+}
+
+/// Settings the monitor enforces on every instance of a named process
+#[derive(Debug, Clone)]
+pub struct ProcessRule {
+    pub name: String,
+    pub affinity_mask: u64,
+    pub priority: Priority,
+    pub scheduling: Option<Scheduling>,
+    pub io_priority: Option<IoPriority>,
+    pub cgroup: Option<CgroupLimits>,
+    pub oom_score_adj: Option<i32>,
+    pub numa: Option<NumaPlacement>,
+    pub thread_rules: Vec<ThreadRule>,
+    // Processes whose presence suspends this rule's processes
+    pub suspend_while_running: Vec<String>,
+    pub instance_limit: Option<InstanceLimit>,
+}
+
+/// What happens to instances started beyond a rule's `max_instances`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum InstanceLimitAction {
+    // Log the extra instance only
+    #[default]
+    Warn,
+    // Run the extra instance at Idle priority until an older instance exits
+    LowerPriority,
+    // Suspend the extra instance until an older instance exits
+    Suspend,
+    // Close the extra instance, forcing it if it doesn't exit
+    TerminateNewest,
+}
+
+/// How many instances of a rule's process may run, and what to do with the rest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstanceLimit {
+    pub max_instances: u32,
+    pub action: InstanceLimitAction,
+}
+
+/// Settings for the threads of a managed process whose name matches a pattern
+#[derive(Debug, Clone)]
+pub struct ThreadRule {
+    // Exact thread name, or a prefix followed by '*'
+    pub name_pattern: String,
+    pub affinity_mask: Option<u64>,
+    pub priority: Option<Priority>,
+}
+
+impl ThreadRule {
+    pub fn matches(&self, thread_name: &str) -> bool {
+        match self.name_pattern.strip_suffix('*') {
+            Some(prefix) => thread_name.to_lowercase().starts_with(&prefix.to_lowercase()),
+            None => thread_name.eq_ignore_ascii_case(&self.name_pattern),
+        }
+    }
+}
+
+impl ProcessRule {
+    /// A rule that only sets affinity and priority, like the single target of legacy mode
+    pub fn new(name: &str, affinity_mask: u64, priority: Priority) -> Self {
+        Self {
+            name: name.to_string(),
+            affinity_mask,
+            priority,
+            scheduling: None,
+            io_priority: None,
+            cgroup: None,
+            oom_score_adj: None,
+            numa: None,
+            thread_rules: Vec::new(),
+            suspend_while_running: Vec::new(),
+            instance_limit: None,
+        }
+    }
+
+    /// Realtime priority and the FIFO/round-robin policies can starve the rest of the system
+    pub fn is_realtime(&self) -> bool {
+        self.priority == Priority::Realtime
+            || self.scheduling.is_some_and(|scheduling| scheduling.policy.is_realtime())
+            || self.thread_rules.iter().any(|thread_rule| thread_rule.priority == Some(Priority::Realtime))
+    }
+
+    /// The same rule with its real-time settings swapped for a safe priority and normal scheduling
+    fn without_realtime(&self, priority: Priority) -> Self {
+        Self {
+            name: self.name.clone(),
+            affinity_mask: self.affinity_mask,
+            priority,
+            scheduling: self.scheduling
+                .filter(|scheduling| scheduling.policy.is_realtime())
+                .map(|_| Scheduling { policy: SchedulingPolicy::Other, rt_priority: 0 })
+                .or(self.scheduling),
+            io_priority: self.io_priority,
+            cgroup: self.cgroup.clone(),
+            oom_score_adj: self.oom_score_adj,
+            numa: self.numa,
+            thread_rules: self.thread_rules
+                .iter()
+                .map(|thread_rule| ThreadRule {
+                    priority: thread_rule.priority.map(|thread_priority| {
+                        if thread_priority == Priority::Realtime { priority } else { thread_priority }
+                    }),
+                    ..thread_rule.clone()
+                })
+                .collect(),
+            suspend_while_running: self.suspend_while_running.clone(),
+            instance_limit: self.instance_limit,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessInstance {
+    pub pid: u32,
+    pub last_applied_affinity: Option<u64>,
+    pub last_applied_priority: Option<Priority>,
+    pub last_applied_scheduling: Option<Scheduling>,
+    pub last_applied_io_priority: Option<IoPriority>,
+    pub last_applied_oom_score_adj: Option<i32>,
+    pub last_applied_numa: Option<NumaPlacement>,
+    // Pages left on other nodes after the last migration, e.g. shared library pages
+    pub numa_unmovable_pages: u64,
+    // TID -> state of threads matched by the rule's thread rules
+    pub threads: HashMap<u32, ThreadInstance>,
+    // Consecutive passes that failed to apply the rule
+    #[serde(default)]
+    pub failures: u32,
+    // Why the manager lacks the rights to apply the rule, while that lasts
+    #[serde(default)]
+    pub unmanageable: Option<String>,
+    // The rule is not applied again before this
+    #[serde(skip)]
+    pub retry_at: Option<Instant>,
+}
+
+impl ProcessInstance {
+    pub fn new(pid: u32) -> Self {
+        Self {
+            pid,
+            last_applied_affinity: None,
+            last_applied_priority: None,
+            last_applied_scheduling: None,
+            last_applied_io_priority: None,
+            last_applied_oom_score_adj: None,
+            last_applied_numa: None,
+            numa_unmovable_pages: 0,
+            threads: HashMap::new(),
+            failures: 0,
+            unmanageable: None,
+            retry_at: None,
+        }
+    }
+
+    /// Whether a failed apply is still waiting for its retry
+    fn retry_pending(&self) -> bool {
+        self.retry_at.is_some_and(|retry_at| Instant::now() < retry_at)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadInstance {
+    pub tid: u32,
+    pub name: String,
+    // Pattern of the thread rule that matched
+    pub rule: String,
+    pub last_applied_affinity: Option<u64>,
+    pub last_applied_priority: Option<Priority>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadDetails {
+    pub tid: u32,
+    pub name: String,
+    pub current_affinity: Option<u64>,
+    pub current_priority: Option<Priority>,
+    pub matched_rule: Option<String>,
+    pub last_applied_affinity: Option<u64>,
+    pub last_applied_priority: Option<Priority>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessDetails {
+    pub pid: u32,
+    pub name: String,
+    pub current_priority: Option<Priority>,
+    pub current_affinity: Option<u64>,
+    pub current_scheduling: Option<Scheduling>,
+    pub current_io_priority: Option<IoPriority>,
+    pub last_applied_priority: Option<Priority>,
+    pub last_applied_affinity: Option<u64>,
+    pub last_applied_scheduling: Option<Scheduling>,
+    pub last_applied_io_priority: Option<IoPriority>,
+    pub cgroup: Option<String>,
+    pub memory_limits: Option<MemoryLimits>,
+    pub current_oom_score_adj: Option<i32>,
+    pub last_applied_oom_score_adj: Option<i32>,
+    pub numa_usage: Option<NumaUsage>,
+    pub last_applied_numa: Option<NumaPlacement>,
+    pub is_tracked: bool,
+}
+
+/// A process the core reservation would move (or has moved) off the reserved cores
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReservationPreview {
+    pub pid: u32,
+    pub name: String,
+    pub current_affinity: u64,
+    pub new_affinity: u64,
+}
+
+/// A process frozen by the manager; resumed at the latest when monitoring stops or the app quits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuspendedProcess {
+    pub pid: u32,
+    pub name: String,
+    // "manual" or the rule that suspended it
+    pub reason: String,
+    // PID whose suspension, together with its descendants, included this process
+    pub root_pid: u32,
+    // Rule whose cgroup was frozen through cgroup.freeze instead of signalling the process
+    pub frozen_group: Option<String>,
+}
+
+/// How `kill_process` ends a process
+#[derive(Debug, Clone, Copy)]
+pub struct TerminateOptions {
+    // Time to wait after a close request before forcing termination; None forces immediately
+    pub graceful_timeout: Option<Duration>,
+    // Also end every descendant of the process
+    pub include_tree: bool,
+    // Allow terminating a process the manager doesn't track
+    pub force: bool,
+}
+
+/// Outcome of a termination, by PID
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TerminationResult {
+    pub signalled: Vec<u32>,
+    pub exited: Vec<u32>,
+    pub survived: Vec<u32>,
+}
+
+/// Wait before retrying after this many consecutive failures, doubling from `RETRY_BASE_DELAY`
+fn retry_delay(failures: u32) -> Duration {
+    RETRY_BASE_DELAY
+        .saturating_mul(1 << failures.saturating_sub(1).min(16))
+        .min(RETRY_MAX_DELAY)
+}
+
+/// A termination under way: the processes have been asked to close, or forced, and are
+/// waited for without the manager, which `finish_kill` then updates
+pub struct PendingTermination {
+    pid: u32,
+    name: String,
+    requested_by: String,
+    force: bool,
+    // Processes not known to have exited yet
+    remaining: Vec<(u32, ProcessHandle)>,
+    result: TerminationResult,
+    // End of the graceful timeout, or of the wait after forcing
+    deadline: Instant,
+    forced: bool,
+}
+
+impl PendingTermination {
+    fn request_close(&mut self, timeout: Duration) {
+        for (target, process) in &self.remaining {
+            if process.request_close().is_ok() {
+                self.result.signalled.push(*target);
+            }
+        }
+        self.deadline = Instant::now() + timeout;
+    }
+
+    fn force(&mut self) {
+        for (target, process) in &self.remaining {
+            if process.terminate().is_ok() && !self.result.signalled.contains(target) {
+                self.result.signalled.push(*target);
+            }
+        }
+        self.forced = true;
+        self.deadline = Instant::now() + FORCED_EXIT_TIMEOUT;
+    }
+
+    /// Check on the processes without blocking, forcing whatever is still running once the
+    /// graceful timeout is over; true once every process has exited or outlived being forced
+    pub fn poll(&mut self) -> bool {
+        let result = &mut self.result;
+        self.remaining.retain(|(target, process)| {
+            let exited = process.wait_for_exit(Duration::ZERO);
+            if exited {
+                result.exited.push(*target);
+            }
+            !exited
+        });
+
+        let expired = Instant::now() >= self.deadline;
+        if self.remaining.is_empty() || (self.forced && expired) {
+            return true;
+        }
+
+        // Processes that couldn't be asked to close have nothing to wait for
+        let waiting_to_close = self.remaining.iter().any(|(target, _)| self.result.signalled.contains(target));
+        if !self.forced && (expired || !waiting_to_close) {
+            self.force();
+        }
+        false
+    }
+
+    /// Wait out the graceful timeout for the processes that were asked to close,
+    /// then force whatever is still running and wait for it to go
+    pub fn wait(&mut self) {
+        if !self.forced {
+            let deadline = self.deadline;
+            let result = &mut self.result;
+            self.remaining.retain(|(target, process)| {
+                let exited = result.signalled.contains(target)
+                    && process.wait_for_exit(deadline.saturating_duration_since(Instant::now()));
+                if exited {
+                    result.exited.push(*target);
+                }
+                !exited
+            });
+            self.force();
+        }
+
+        for (target, process) in self.remaining.drain(..) {
+            if process.wait_for_exit(self.deadline.saturating_duration_since(Instant::now())) {
+                self.result.exited.push(target);
+            } else {
+                self.result.survived.push(target);
+            }
+        }
+    }
+}
+
+/// A termination request as recorded in the kill audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KillAuditEntry {
+    pub timestamp: DateTime<Local>,
+    pub pid: u32,
+    pub name: String,
+    // "user action" or the rule that asked for the kill
+    pub requested_by: String,
+    pub force: bool,
+    pub outcome: String,
+}
+
+/// Affinity a process had before the core reservation restricted it
+#[derive(Debug, Clone, Copy)]
+struct ReservedProcess {
+    original_affinity: u64,
+    // Tells the restricted process apart from a later one that reuses its PID
+    start_time: Option<Duration>,
+}
+
+/// Settings a process had before background demotion changed them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DemotedProcess {
+    pub pid: u32,
+    pub name: String,
+    pub original_priority: Priority,
+    pub original_affinity: u64,
+}
+
+/// How the CPU hog watchdog detects runaway processes and what it does to them
+#[derive(Debug, Clone)]
+pub struct CpuHogPolicy {
+    // 100% is one fully busy core
+    pub cpu_threshold_percent: f64,
+    pub sustained: Duration,
+    pub calm: Duration,
+    pub priority: Priority,
+    pub affinity_mask: Option<u64>,
+}
+
+/// When the real-time guard steps in on a process running under a real-time rule
+#[derive(Debug, Clone)]
+pub struct RealtimeGuardPolicy {
+    // Usage that counts as a fully busy core; slightly below 100% to absorb sampling jitter
+    pub cpu_threshold_percent: f64,
+    pub max_full_core: Duration,
+    pub calm: Duration,
+    pub priority: Priority,
+}
+
+// Per-process CPU time bookkeeping for the hog watchdog and the real-time guard
+struct CpuSample {
+    cpu_time: Duration,
+    sampled_at: Instant,
+    over_threshold_since: Option<Instant>,
+    under_threshold_since: Option<Instant>,
+}
+
+impl CpuSample {
+    fn new(cpu_time: Duration, now: Instant) -> Self {
+        Self {
+            cpu_time,
+            sampled_at: now,
+            over_threshold_since: None,
+            under_threshold_since: None,
+        }
+    }
+
+    /// Record a new CPU time reading and return the usage since the previous one,
+    /// where 100% is one fully busy core
+    fn record(&mut self, cpu_time: Duration, now: Instant, threshold_percent: f64) -> Option<f64> {
+        let elapsed = now.duration_since(self.sampled_at).as_secs_f64();
+        if elapsed <= 0.0 {
+            return None;
+        }
+
+        let cpu_seconds = cpu_time.saturating_sub(self.cpu_time).as_secs_f64();
+        let cpu_percent = cpu_seconds / elapsed * 100.0;
+        self.cpu_time = cpu_time;
+        self.sampled_at = now;
+
+        if cpu_percent > threshold_percent {
+            self.over_threshold_since.get_or_insert(now);
+            self.under_threshold_since = None;
+        } else {
+            self.under_threshold_since.get_or_insert(now);
+            self.over_threshold_since = None;
+        }
+
+        Some(cpu_percent)
+    }
+
+    fn over_threshold_for(&self, now: Instant) -> Option<Duration> {
+        self.over_threshold_since.map(|since| now.duration_since(since))
+    }
+
+    fn under_threshold_for(&self, now: Instant) -> Option<Duration> {
+        self.under_threshold_since.map(|since| now.duration_since(since))
+    }
+}
+
+/// What one monitoring pass changed on a process
+#[derive(Debug, Clone, Default)]
+pub struct AppliedChanges {
+    pub descriptions: Vec<String>,
+    // (before, after) of the affinity mask and priority, when they were changed
+    pub affinity: Option<(u64, u64)>,
+    pub priority: Option<(Priority, Priority)>,
+}
+
+impl AppliedChanges {
+    pub fn summary(&self) -> String {
+        self.descriptions.join(", ")
+    }
+}
+
+pub struct ProcessManager {
+    tracked_processes: HashMap<u32, ProcessInstance>,
+    // PID -> affinity mask the process had before the core reservation restricted it
+    reservation_originals: HashMap<u32, ReservedProcess>,
+    // PID -> settings of processes lowered by background demotion
+    demoted_processes: HashMap<u32, DemotedProcess>,
+    // PID -> CPU usage samples and settings of processes demoted by the hog watchdog
+    cpu_samples: HashMap<u32, CpuSample>,
+    cpu_hogs: HashMap<u32, DemotedProcess>,
+    // PID -> CPU usage samples and stand-in rules of real-time processes held down by the guard
+    realtime_samples: HashMap<u32, CpuSample>,
+    realtime_demotions: HashMap<u32, ProcessRule>,
+    // Managed cgroup v2 subtree, present while rules with cgroup limits are monitored
+    cgroups: Option<CgroupTree>,
+    // PID -> processes frozen manually or by suspend rules
+    suspended_processes: HashMap<u32, SuspendedProcess>,
+    // The newest kill requests of this session, refused ones included, and the file they are appended to
+    kill_audit: VecDeque<KillAuditEntry>,
+    kill_audit_path: PathBuf,
+    // Rule name -> PIDs of its running instances, from the last monitoring pass
+    rule_instances: HashMap<String, Vec<u32>>,
+    // PID -> action taken on an instance beyond its rule's limit
+    extra_instances: HashMap<u32, InstanceLimitAction>,
+    // PID -> rule applied instead of the configured one to extra instances at lowered priority
+    lowered_instances: HashMap<u32, ProcessRule>,
+    // Extra instances asked to close, with their rule, checked on each pass until they are gone
+    closing_instances: Vec<(String, PendingTermination)>,
+    // Events and per-process log entries of the last monitoring pass, until the engine takes them
+    events: Vec<EngineEvent>,
+    logs: Vec<LogEntry>,
+}
+
+impl ProcessManager {
+    pub fn new() -> Self {
+        Self {
+            tracked_processes: HashMap::new(),
+            reservation_originals: HashMap::new(),
+            demoted_processes: HashMap::new(),
+            cpu_samples: HashMap::new(),
+            cpu_hogs: HashMap::new(),
+            realtime_samples: HashMap::new(),
+            realtime_demotions: HashMap::new(),
+            cgroups: None,
+            suspended_processes: HashMap::new(),
+            kill_audit: VecDeque::new(),
+            kill_audit_path: log_files::default_log_directory().join(KILL_AUDIT_LOG),
+            rule_instances: HashMap::new(),
+            extra_instances: HashMap::new(),
+            lowered_instances: HashMap::new(),
+            closing_instances: Vec::new(),
+            events: Vec::new(),
+            logs: Vec::new(),
+        }
+    }
+
+    /// Events queued since the last call
+    pub fn take_events(&mut self) -> Vec<EngineEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Log entries queued since the last call
+    pub fn take_logs(&mut self) -> Vec<LogEntry> {
+        std::mem::take(&mut self.logs)
+    }
+
+    /// Manage the single target of legacy mode; events are queued and instances recorded for
+    /// `enforce_instance_limits` like in multi-process mode
+    pub fn check_and_manage_process(&mut self, rule: &ProcessRule) -> ProcessState {
+        let process_name = rule.name.as_str();
+
+        match self.find_all_processes_by_name(process_name) {
+            Ok(pids) => {
+                let previous_pids = self.rule_instances.remove(process_name).unwrap_or_default();
+                for &pid in previous_pids.iter().filter(|pid| !pids.contains(pid)) {
+                    self.events.push(EngineEvent::ProcessExited { rule: process_name.to_string(), pid });
+                }
+                for &pid in pids.iter().filter(|pid| !previous_pids.contains(pid)) {
+                    self.events.push(EngineEvent::ProcessFound { rule: process_name.to_string(), pid });
+                }
+                if !pids.is_empty() {
+                    self.rule_instances.insert(process_name.to_string(), pids.clone());
+                }
+
+                if pids.is_empty() {
+                    // No processes found, clear tracking
+                    self.tracked_processes.clear();
+                    ProcessState::NotFound
+                } else if pids.len() == 1 {
+                    // Single process - maintain backward compatibility
+                    let pid = pids[0];
+                    let is_new_process = !self.tracked_processes.contains_key(&pid);
+
+                    if is_new_process {
+                        self.tracked_processes.insert(pid, ProcessInstance::new(pid));
+                    }
+
+                    if self.tracked_processes.get(&pid).is_some_and(ProcessInstance::retry_pending) {
+                        return ProcessState::FoundAndMonitoring(pid);
+                    }
+
+                    match self.monitor_and_reapply_settings_for_pid(pid, rule) {
+                        Ok(changes_applied) => {
+                            self.record_success(pid);
+                            if is_new_process {
+                                ProcessState::Found(pid)
+                            } else if !changes_applied.is_empty() {
+                                self.events.push(EngineEvent::SettingsReapplied {
+                                    rule: process_name.to_string(),
+                                    pid,
+                                    changes: changes_applied.clone(),
+                                });
+                                ProcessState::SettingsApplied(pid, changes_applied)
+                            } else {
+                                ProcessState::FoundAndMonitoring(pid)
+                            }
+                        }
+                        Err(err) => match self.record_failure(pid, err) {
+                            Some(err) => {
+                                let message = format!("Failed to monitor PID {}: {}", pid, err);
+                                self.events.push(EngineEvent::Error { message: message.clone() });
+                                ProcessState::Error(message)
+                            }
+                            None => ProcessState::FoundAndMonitoring(pid),
+                        },
+                    }
+                } else {
+                    // Multiple processes found
+                    self.handle_multiple_processes(pids, rule)
+                }
+            }
+            Err(err) => {
+                let message = format!("Process enumeration failed: {}", err);
+                self.events.push(EngineEvent::Error { message: message.clone() });
+                ProcessState::Error(message)
+            }
+        }
+    }
+
+    fn handle_multiple_processes(&mut self, pids: Vec<u32>, rule: &ProcessRule) -> ProcessState {
+        let mut new_processes = Vec::new();
+        let mut monitoring_processes = Vec::new();
+        let mut settings_applied = Vec::new();
+        let mut errors = Vec::new();
+
+        // Remove processes that are no longer running
+        let current_pids: std::collections::HashSet<u32> = pids.iter().cloned().collect();
+        self.tracked_processes.retain(|&pid, _| current_pids.contains(&pid));
+
+        for pid in pids {
+            let is_new_process = !self.tracked_processes.contains_key(&pid);
+
+            if is_new_process {
+                self.tracked_processes.insert(pid, ProcessInstance::new(pid));
+                new_processes.push(pid);
+            }
+
+            if self.tracked_processes.get(&pid).is_some_and(ProcessInstance::retry_pending) {
+                monitoring_processes.push(pid);
+                continue;
+            }
+
+            match self.monitor_and_reapply_settings_for_pid(pid, rule) {
+                Ok(changes_applied) => {
+                    self.record_success(pid);
+                    if !changes_applied.is_empty() {
+                        if !is_new_process {
+                            self.events.push(EngineEvent::SettingsReapplied {
+                                rule: rule.name.clone(),
+                                pid,
+                                changes: changes_applied.clone(),
+                            });
+                        }
+                        settings_applied.push((pid, changes_applied));
+                    } else {
+                        monitoring_processes.push(pid);
+                    }
+                }
+                Err(err) => {
+                    if let Some(err) = self.record_failure(pid, err) {
+                        errors.push(format!("PID {}: {}", pid, err));
+                    }
+                }
+            }
+        }
+
+        self.events.extend(errors.iter().map(|message| EngineEvent::Error { message: message.clone() }));
+
+        // Return appropriate state based on what happened
+        if !errors.is_empty() {
+            ProcessState::Error(format!("Errors: {}", errors.join("; ")))
+        } else if !new_processes.is_empty() {
+            ProcessState::MultipleFound(new_processes)
+        } else if !settings_applied.is_empty() {
+            ProcessState::MultipleSettingsApplied(settings_applied)
+        } else {
+            ProcessState::MultipleMonitoring(monitoring_processes)
+        }
+    }
+
+
+
+    pub fn find_all_processes_by_name(&self, process_name: &str) -> Result<Vec<u32>, ManagerError> {
+        let target = process_name.to_lowercase();
+        Ok(self.snapshot_processes()?
+            .into_iter()
+            .filter(|(_, exe_name)| exe_name.to_lowercase() == target)
+            .map(|(pid, _)| pid)
+            .collect())
+    }
+
+    /// Take a snapshot of every running process as (PID, executable name) pairs
+    fn snapshot_processes(&self) -> Result<Vec<(u32, String)>, ManagerError> {
+        platform::snapshot_processes()
+    }
+
+    fn monitor_and_reapply_settings_for_pid(&mut self, pid: u32, rule: &ProcessRule) -> Result<String, ManagerError> {
+        self.reapply_settings(pid, rule).map(|changes| changes.summary())
+    }
+
+    fn reapply_settings(&mut self, pid: u32, rule: &ProcessRule) -> Result<AppliedChanges, ManagerError> {
+        // A real-time process held down by the guard keeps its demoted settings until the guard restores it
+        // and an extra instance at lowered priority stays lowered while it is beyond the limit
+        let demoted_rule = self.realtime_demotions.get(&pid).or_else(|| self.lowered_instances.get(&pid)).cloned();
+        let rule = demoted_rule.as_ref().unwrap_or(rule);
+
+        let desired_affinity = rule.affinity_mask;
+        let desired_priority = rule.priority;
+        let process = ProcessHandle::open_for_update(pid)?;
+
+        let mut changes = AppliedChanges::default();
+
+        // Get the process instance for tracking
+        let process_instance = self.tracked_processes.get(&pid).cloned();
+        let last_applied_affinity = process_instance.as_ref().and_then(|p| p.last_applied_affinity);
+        let last_applied_priority = process_instance.as_ref().and_then(|p| p.last_applied_priority);
+        let last_applied_scheduling = process_instance.as_ref().and_then(|p| p.last_applied_scheduling);
+        let last_applied_io_priority = process_instance.as_ref().and_then(|p| p.last_applied_io_priority);
+        let last_applied_oom_score_adj = process_instance.as_ref().and_then(|p| p.last_applied_oom_score_adj);
+        let last_applied_numa = process_instance.as_ref().and_then(|p| p.last_applied_numa);
+        let numa_unmovable_pages = process_instance.as_ref().map_or(0, |p| p.numa_unmovable_pages);
+
+        // Threads under a thread rule have their own settings, so the process-wide
+        // values are read from the first thread no thread rule claims
+        let threads = if rule.thread_rules.is_empty() { Vec::new() } else { process.threads() };
+        let reference_tid = threads
+            .iter()
+            .find(|(_, name)| !rule.thread_rules.iter().any(|thread_rule| thread_rule.matches(name)))
+            .map(|(tid, _)| *tid);
+
+        // The process-wide setters leave these threads alone, or the two would undo each other every cycle
+        let claimed_tids = |setting: fn(&ThreadRule) -> bool| -> Vec<u32> {
+            threads
+                .iter()
+                .filter(|(_, name)| rule.thread_rules.iter().find(|thread_rule| thread_rule.matches(name)).is_some_and(setting))
+                .map(|(tid, _)| *tid)
+                .collect()
+        };
+
+        // Check and reapply CPU affinity
+        let current_affinity = match reference_tid {
+            Some(tid) => process.thread_affinity(tid)?,
+            None => process.affinity()?,
+        };
+
+        if current_affinity != desired_affinity || last_applied_affinity != Some(desired_affinity) {
+            let claimed = claimed_tids(|thread_rule| thread_rule.affinity_mask.is_some());
+            Self::reapply_affinity(&process, current_affinity, desired_affinity, &claimed, &mut changes)?;
+            // Update the tracked process instance
+            if let Some(instance) = self.tracked_processes.get_mut(&pid) {
+                instance.last_applied_affinity = Some(desired_affinity);
+            }
+        }
+
+        // Check and reapply priority
+        let current_priority = match reference_tid {
+            Some(tid) => process.thread_priority(tid)?,
+            None => process.priority()?,
+        };
+
+        if current_priority != desired_priority || last_applied_priority != Some(desired_priority) {
+            process.set_priority(desired_priority, &claimed_tids(|thread_rule| thread_rule.priority.is_some()))?;
+            changes.descriptions.push(format!("Priority: {} → {}", current_priority, desired_priority));
+            changes.priority = Some((current_priority, desired_priority));
+            // Update the tracked process instance
+            if let Some(instance) = self.tracked_processes.get_mut(&pid) {
+                instance.last_applied_priority = Some(desired_priority);
+            }
+        }
+
+        // Check and reapply scheduling policy, when the rule sets one
+        if let Some(desired_scheduling) = rule.scheduling {
+            let current_scheduling = process.scheduling()?;
+
+            if current_scheduling != desired_scheduling || last_applied_scheduling != Some(desired_scheduling) {
+                process.set_scheduling(desired_scheduling)?;
+                changes.descriptions.push(format!("Scheduling: {} → {}", current_scheduling, desired_scheduling));
+                // Update the tracked process instance
+                if let Some(instance) = self.tracked_processes.get_mut(&pid) {
+                    instance.last_applied_scheduling = Some(desired_scheduling);
+                }
+            }
+        }
+
+        // Check and reapply I/O priority, when the rule sets one
+        if let Some(desired_io_priority) = rule.io_priority {
+            let current_io_priority = process.io_priority()?;
+
+            if current_io_priority != desired_io_priority || last_applied_io_priority != Some(desired_io_priority) {
+                process.set_io_priority(desired_io_priority)?;
+                changes.descriptions.push(format!("I/O priority: {} → {}", current_io_priority, desired_io_priority));
+                // Update the tracked process instance
+                if let Some(instance) = self.tracked_processes.get_mut(&pid) {
+                    instance.last_applied_io_priority = Some(desired_io_priority);
+                }
+            }
+        }
+
+        // Check and reapply the OOM score adjustment, when the rule sets one
+        if let Some(desired_oom_score_adj) = rule.oom_score_adj {
+            let current_oom_score_adj = process.oom_score_adj()?;
+
+            if current_oom_score_adj != desired_oom_score_adj || last_applied_oom_score_adj != Some(desired_oom_score_adj) {
+                process.set_oom_score_adj(desired_oom_score_adj)?;
+                changes.descriptions.push(format!("OOM score adj: {} → {}", current_oom_score_adj, desired_oom_score_adj));
+                // Update the tracked process instance
+                if let Some(instance) = self.tracked_processes.get_mut(&pid) {
+                    instance.last_applied_oom_score_adj = Some(desired_oom_score_adj);
+                }
+            }
+        }
+
+        // Check and reapply thread rules; threads started since the last cycle are picked up here
+        if !rule.thread_rules.is_empty() {
+            changes.descriptions.extend(self.reapply_thread_rules(pid, &process, &threads, &rule.thread_rules)?);
+        }
+
+        // Check and reapply NUMA placement, when the rule sets one; pages that wouldn't move
+        // last time are only retried once more memory has strayed onto other nodes. A process
+        // whose placement can't be read, e.g. a kernel thread, skips the step
+        if let Some((desired_numa, usage)) = rule.numa.and_then(|numa| Some((numa, process.numa_usage().ok()?))) {
+            let stray_pages = usage.pages_outside(desired_numa.node_mask);
+
+            if last_applied_numa != Some(desired_numa) || stray_pages > numa_unmovable_pages {
+                let other_nodes = usage.pages_per_node
+                    .keys()
+                    .filter(|node| **node < 64)
+                    .fold(0u64, |acc, node| acc | (1u64 << node))
+                    & !desired_numa.node_mask;
+
+                if other_nodes != 0 {
+                    process.migrate_pages(other_nodes, desired_numa.node_mask)?;
+                }
+                let unmovable_pages = process.numa_usage()
+                    .map(|usage| usage.pages_outside(desired_numa.node_mask))
+                    .unwrap_or(stray_pages);
+
+                // Without a cgroup nothing stops new allocations elsewhere; say so where the change is shown
+                let enforcement = if rule.cgroup.is_some() { "" } else { ", migration only" };
+                changes.descriptions.push(format!(
+                    "NUMA: {} ({} pages moved{})",
+                    desired_numa,
+                    stray_pages.saturating_sub(unmovable_pages),
+                    enforcement
+                ));
+                // Update the tracked process instance
+                if let Some(instance) = self.tracked_processes.get_mut(&pid) {
+                    instance.last_applied_numa = Some(desired_numa);
+                    instance.numa_unmovable_pages = unmovable_pages;
+                }
+            }
+        }
+
+        // Keep the process in its rule's cgroup and the group at the rule's limits;
+        // a NUMA binding also confines the group's future allocations to its nodes
+        if let (Some(limits), Some(cgroups)) = (&rule.cgroup, self.cgroups.as_mut()) {
+            let memory_nodes = rule.numa.map(|numa| numa.node_mask);
+            changes.descriptions.extend(cgroups.apply_limits(&rule.name, limits, rule.affinity_mask, memory_nodes)?);
+            changes.descriptions.extend(cgroups.add_process(&rule.name, pid)?);
+        }
+
+        Ok(changes)
+    }
+
+    /// Set a process's affinity and record the change; rules and the core reservation both go through here
+    fn reapply_affinity(process: &ProcessHandle, current_affinity: u64, desired_affinity: u64, claimed_tids: &[u32], changes: &mut AppliedChanges) -> Result<(), ManagerError> {
+        process.set_affinity(desired_affinity, claimed_tids)?;
+        changes.descriptions.push(format!("CPU affinity: 0x{:X} → 0x{:X}", current_affinity, desired_affinity));
+        changes.affinity = Some((current_affinity, desired_affinity));
+        Ok(())
+    }
+
+    /// Emit settings reapplied to a running process, returning the entry that logs them
+    fn report_reapplied(&mut self, level: LogLevel, rule: &str, process_name: &str, pid: u32, changes: &AppliedChanges) -> LogEntry {
+        let changes_applied = changes.summary();
+        self.events.push(EngineEvent::SettingsReapplied {
+            rule: rule.to_string(),
+            pid,
+            changes: changes_applied.clone(),
+        });
+
+        let mut entry = LogEntry::new(
+            level,
+            format!("{} (PID {}) settings reapplied: {}", process_name, pid, changes_applied),
+        )
+        .with_process(rule, pid);
+        if let Some((old, new)) = changes.affinity {
+            entry = entry.with_affinity(old, new);
+        }
+        if let Some((old, new)) = changes.priority {
+            entry = entry.with_priority(old, new);
+        }
+        entry
+    }
+
+    fn reapply_thread_rules(&mut self, pid: u32, process: &ProcessHandle, threads: &[(u32, String)], thread_rules: &[ThreadRule]) -> Result<Vec<String>, ManagerError> {
+        let mut changes_applied = Vec::new();
+        let Some(instance) = self.tracked_processes.get_mut(&pid) else {
+            return Ok(changes_applied);
+        };
+
+        // Forget threads that have exited
+        instance.threads.retain(|tid, _| threads.iter().any(|(thread_tid, _)| thread_tid == tid));
+
+        for (tid, name) in threads {
+            let Some(thread_rule) = thread_rules.iter().find(|thread_rule| thread_rule.matches(name)) else {
+                continue;
+            };
+
+            let thread = instance.threads.entry(*tid).or_insert_with(|| ThreadInstance {
+                tid: *tid,
+                name: name.clone(),
+                rule: thread_rule.name_pattern.clone(),
+                last_applied_affinity: None,
+                last_applied_priority: None,
+            });
+
+            // A thread that exits between listing and reading is skipped until the next cycle
+            if let Some(desired_affinity) = thread_rule.affinity_mask {
+                if let Ok(current_affinity) = process.thread_affinity(*tid) {
+                    if current_affinity != desired_affinity || thread.last_applied_affinity != Some(desired_affinity) {
+                        process.set_thread_affinity(*tid, desired_affinity)?;
+                        changes_applied.push(format!("Thread {} (TID {}) CPU affinity: 0x{:X} → 0x{:X}", name, tid, current_affinity, desired_affinity));
+                        thread.last_applied_affinity = Some(desired_affinity);
+                    }
+                }
+            }
+
+            if let Some(desired_priority) = thread_rule.priority {
+                if let Ok(current_priority) = process.thread_priority(*tid) {
+                    if current_priority != desired_priority || thread.last_applied_priority != Some(desired_priority) {
+                        process.set_thread_priority(*tid, desired_priority)?;
+                        changes_applied.push(format!("Thread {} (TID {}) priority: {} → {}", name, tid, current_priority, desired_priority));
+                        thread.last_applied_priority = Some(desired_priority);
+                    }
+                }
+            }
+        }
+
+        Ok(changes_applied)
+    }
+
+    pub fn is_running_as_administrator() -> bool {
+        platform::is_elevated()
+    }
+
+    /// Get the number of logical processors (CPU cores) available on the system
+    pub fn get_system_cpu_count() -> u32 {
+        platform::cpu_count()
+    }
+
+    /// Get the system's processor affinity mask to determine available cores
+    pub fn get_system_affinity_mask() -> u64 {
+        platform::system_affinity_mask().unwrap_or_else(|| {
+            // Fallback: assume all cores up to CPU count are available
+            let cpu_count = Self::get_system_cpu_count();
+            if cpu_count < 64 {
+                (1u64 << cpu_count) - 1
+            } else {
+                u64::MAX // All 64 bits set for systems with 64 or more cores
+            }
+        })
+    }
+
+    /// Start tracking a process without applying anything yet
+    pub fn track_process(&mut self, pid: u32) {
+        self.tracked_processes.entry(pid).or_insert(ProcessInstance::new(pid));
+    }
+
+    /// Decide what a failed apply means for a tracked process, returning the error if it should be reported.
+    /// A process that exited is forgotten quietly; one the manager lacks the rights for is marked
+    /// unmanageable and only tried again now and then; anything else is retried with backoff
+    fn record_failure(&mut self, pid: u32, err: ManagerError) -> Option<ManagerError> {
+        if err.is_process_exited() {
+            self.tracked_processes.remove(&pid);
+            return None;
+        }
+        let instance = self.tracked_processes.get_mut(&pid)?;
+        instance.failures += 1;
+
+        if matches!(err, ManagerError::AccessDenied { .. } | ManagerError::ProtectedProcess { .. }) {
+            instance.retry_at = Some(Instant::now() + UNMANAGEABLE_RETRY_INTERVAL);
+            // Reported when first found unmanageable, not on every slow retry
+            let first = instance.unmanageable.is_none();
+            instance.unmanageable = Some(err.to_string());
+            return first.then(|| err.context(format_args!("unmanageable, retrying every {} minutes", UNMANAGEABLE_RETRY_INTERVAL.as_secs() / 60)));
+        }
+
+        instance.retry_at = Some(Instant::now() + retry_delay(instance.failures));
+        Some(err)
+    }
+
+    fn record_success(&mut self, pid: u32) {
+        if let Some(instance) = self.tracked_processes.get_mut(&pid) {
+            instance.failures = 0;
+            instance.unmanageable = None;
+            instance.retry_at = None;
+        }
+    }
+
+    /// Apply a rule to a single process once, tracking it; returns the changes made
+    pub fn apply_rule(&mut self, pid: u32, rule: &ProcessRule) -> Result<String, ManagerError> {
+        self.track_process(pid);
+        self.monitor_and_reapply_settings_for_pid(pid, rule)
+    }
+
+    /// Check and manage multiple different processes with individual settings
+    pub fn check_and_manage_multiple_processes(&mut self, rules: &[ProcessRule]) -> ProcessState {
+        if rules.is_empty() {
+            self.tracked_processes.clear();
+            return ProcessState::NotFound;
+        }
+
+        let mut process_results: HashMap<String, Vec<u32>> = HashMap::new();
+        let mut new_processes: HashMap<String, Vec<u32>> = HashMap::new();
+        let mut monitoring_processes: HashMap<String, Vec<u32>> = HashMap::new();
+        let mut settings_applied: HashMap<String, Vec<(u32, String)>> = HashMap::new();
+        let mut errors = Vec::new();
+
+        // Get all currently running PIDs for all target processes
+        let mut all_current_pids = std::collections::HashSet::new();
+        for rule in rules {
+            let process_name = &rule.name;
+            match self.find_all_processes_by_name(process_name) {
+                Ok(pids) => {
+                    if !pids.is_empty() {
+                        process_results.insert(process_name.clone(), pids.clone());
+                        all_current_pids.extend(pids.iter().cloned());
+                    }
+                }
+                Err(err) => {
+                    let message = format!("{}: {}", process_name, err);
+                    self.logs.push(LogEntry::new(LogLevel::Error, message.clone()).with_rule(process_name));
+                    errors.push(message);
+                }
+            }
+        }
+
+        for (rule_name, pids) in &self.rule_instances {
+            for &pid in pids.iter().filter(|pid| !all_current_pids.contains(pid)) {
+                self.events.push(EngineEvent::ProcessExited { rule: rule_name.clone(), pid });
+            }
+        }
+
+        // Remove tracked processes that are no longer running
+        self.tracked_processes.retain(|&pid, _| all_current_pids.contains(&pid));
+        self.rule_instances = process_results.clone();
+
+        // Process each target process
+        for rule in rules {
+            let process_name = &rule.name;
+            if let Some(pids) = process_results.get(process_name) {
+                let mut process_new = Vec::new();
+                let mut process_monitoring = Vec::new();
+                let mut process_settings = Vec::new();
+
+                for &pid in pids {
+                    let is_new_process = !self.tracked_processes.contains_key(&pid);
+
+                    if is_new_process {
+                        self.tracked_processes.insert(pid, ProcessInstance::new(pid));
+                        process_new.push(pid);
+                        self.events.push(EngineEvent::ProcessFound { rule: process_name.clone(), pid });
+                    }
+
+                    if self.tracked_processes.get(&pid).is_some_and(ProcessInstance::retry_pending) {
+                        process_monitoring.push(pid);
+                        continue;
+                    }
+
+                    match self.reapply_settings(pid, rule) {
+                        Ok(changes) => {
+                            self.record_success(pid);
+                            let changes_applied = changes.summary();
+                            if !is_new_process && !changes_applied.is_empty() {
+                                let entry = self.report_reapplied(LogLevel::Reapply, process_name, process_name, pid, &changes);
+                                self.logs.push(entry);
+                            }
+
+                            if !changes_applied.is_empty() {
+                                process_settings.push((pid, changes_applied));
+                            } else {
+                                process_monitoring.push(pid);
+                            }
+                        }
+                        Err(err) => {
+                            if let Some(err) = self.record_failure(pid, err) {
+                                let message = format!("{} PID {}: {}", process_name, pid, err);
+                                self.logs.push(LogEntry::new(LogLevel::Error, message.clone()).with_process(process_name, pid));
+                                errors.push(message);
+                            }
+                        }
+                    }
+                }
+
+                if !process_new.is_empty() {
+                    new_processes.insert(process_name.clone(), process_new);
+                } else if !process_settings.is_empty() {
+                    settings_applied.insert(process_name.clone(), process_settings);
+                } else if !process_monitoring.is_empty() {
+                    monitoring_processes.insert(process_name.clone(), process_monitoring);
+                }
+            }
+        }
+
+        self.events.extend(errors.iter().map(|message| EngineEvent::Error { message: message.clone() }));
+
+        // Return appropriate state based on what happened
+        if !errors.is_empty() {
+            ProcessState::Error(format!("Errors: {}", errors.join("; ")))
+        } else if !new_processes.is_empty() {
+            ProcessState::MultiProcessFound(new_processes)
+        } else if !settings_applied.is_empty() {
+            ProcessState::MultiProcessSettingsApplied(settings_applied)
+        } else if !monitoring_processes.is_empty() {
+            ProcessState::MultiProcessMonitoring(monitoring_processes)
+        } else {
+            ProcessState::NotFound
+        }
+    }
+
+    /// Whether a process must never be touched by rules that act on every process
+    pub fn is_protected_process(pid: u32, exe_name: &str, protected_processes: &[String]) -> bool {
+        platform::is_system_process(pid)
+            || pid == std::process::id()
+            || protected_processes.iter().any(|name| name.eq_ignore_ascii_case(exe_name))
+    }
+
+    /// Remove the reserved cores from an affinity mask, falling back to every
+    /// unreserved core when the process was confined to reserved cores only
+    pub fn restrict_to_unreserved_cores(current_affinity: u64, reserved_mask: u64, available_mask: u64) -> u64 {
+        let remaining = current_affinity & !reserved_mask;
+        if remaining != 0 {
+            remaining
+        } else {
+            available_mask & !reserved_mask
+        }
+    }
+
+    /// Whether a catch-all action must skip a process, either protected or exempted by name
+    fn is_exempt_process(pid: u32, exe_name: &str, exempt_processes: &[String], protected_processes: &[String]) -> bool {
+        Self::is_protected_process(pid, exe_name, protected_processes)
+            || exempt_processes.iter().any(|name| name.eq_ignore_ascii_case(exe_name))
+    }
+
+    /// List every process the core reservation would restrict, without changing anything
+    pub fn preview_core_reservation(&self, reserved_mask: u64, exempt_processes: &[String], protected_processes: &[String]) -> Result<Vec<ReservationPreview>, ManagerError> {
+        let available_mask = Self::get_system_affinity_mask();
+        let mut previews = Vec::new();
+
+        for (pid, exe_name) in self.snapshot_processes()? {
+            if Self::is_exempt_process(pid, &exe_name, exempt_processes, protected_processes) {
+                continue;
+            }
+
+            // Processes we can't open for update would be left alone, so they aren't listed
+            let Some(process) = ProcessHandle::try_open_for_update(pid) else {
+                continue;
+            };
+
+            if let Ok(current_affinity) = process.affinity() {
+                let new_affinity = Self::restrict_to_unreserved_cores(current_affinity, reserved_mask, available_mask);
+                if new_affinity != current_affinity {
+                    previews.push(ReservationPreview {
+                        pid,
+                        name: exe_name,
+                        current_affinity,
+                        new_affinity,
+                    });
+                }
+            }
+        }
+
+        Ok(previews)
+    }
+
+    /// Move every non-exempt, non-protected process off the reserved cores, remembering its
+    /// original mask so the reservation can be released later. Each move is reported as a
+    /// reapply under the core reservation's rule name, in the pass's logs and events
+    pub fn enforce_core_reservation(&mut self, reserved_mask: u64, exempt_processes: &[String], protected_processes: &[String]) -> Vec<LogEntry> {
+        let snapshot = match self.snapshot_processes() {
+            Ok(snapshot) => snapshot,
+            Err(err) => return vec![LogEntry::new(LogLevel::Error, format!("Core reservation failed: {}", err))],
+        };
+
+        // Forget original masks of processes that have exited
+        let running_pids: std::collections::HashSet<u32> = snapshot.iter().map(|(pid, _)| *pid).collect();
+        self.reservation_originals.retain(|pid, _| running_pids.contains(pid));
+
+        let available_mask = Self::get_system_affinity_mask();
+        let mut logs = Vec::new();
+
+        for (pid, exe_name) in snapshot {
+            if Self::is_exempt_process(pid, &exe_name, exempt_processes, protected_processes) {
+                continue;
+            }
+
+            // Processes in other sessions or protected services are left alone
+            let Some(process) = ProcessHandle::try_open_for_update(pid) else {
+                continue;
+            };
+
+            let start_time = process.start_time();
+            // A reused PID is a different process, whose own mask is the one to keep
+            if self.reservation_originals.get(&pid).is_some_and(|reserved| reserved.start_time != start_time) {
+                self.reservation_originals.remove(&pid);
+            }
+
+            if let Ok(current_affinity) = process.affinity() {
+                let new_affinity = Self::restrict_to_unreserved_cores(current_affinity, reserved_mask, available_mask);
+                let mut changes = AppliedChanges::default();
+                if new_affinity != current_affinity
+                    && Self::reapply_affinity(&process, current_affinity, new_affinity, &[], &mut changes).is_ok()
+                {
+                    self.reservation_originals.entry(pid).or_insert(ReservedProcess {
+                        original_affinity: current_affinity,
+                        start_time,
+                    });
+                    logs.push(self.report_reapplied(LogLevel::Reserve, CORE_RESERVATION_RULE, &exe_name, pid, &changes));
+                }
+            }
+        }
+
+        logs
+    }
+
+    /// Give every process restricted by the core reservation its original affinity back
+    pub fn release_core_reservation(&mut self) -> Vec<LogEntry> {
+        if self.reservation_originals.is_empty() {
+            return Vec::new();
+        }
+
+        let restored = self.reservation_originals
+            .drain()
+            // Processes that exited in the meantime have nothing to restore, even if their PID is in use again
+            .filter(|&(pid, reserved)| {
+                ProcessHandle::try_open_for_update(pid)
+                    .filter(|process| process.start_time() == reserved.start_time)
+                    .is_some_and(|process| process.set_affinity(reserved.original_affinity, &[]).is_ok())
+            })
+            .count();
+
+        vec![LogEntry::new(LogLevel::Reserve, format!("Core reservation released: restored the original affinity of {} processes.", restored))]
+    }
+
+    /// Demote every process owned by the current user that is neither allowlisted
+    /// nor protected, remembering its settings so they can be restored later
+    pub fn enforce_background_demotion(&mut self, allowlist: &[String], protected_processes: &[String], priority: Priority, affinity_mask: Option<u64>) -> Vec<LogEntry> {
+        let snapshot = match self.snapshot_processes() {
+            Ok(snapshot) => snapshot,
+            Err(err) => return vec![LogEntry::new(LogLevel::Error, format!("Background demotion failed: {}", err))],
+        };
+
+        // Forget settings of processes that have exited
+        let running_pids: std::collections::HashSet<u32> = snapshot.iter().map(|(pid, _)| *pid).collect();
+        self.demoted_processes.retain(|pid, _| running_pids.contains(pid));
+
+        let Some(current_user_id) = platform::current_user_id() else {
+            return vec![LogEntry::new(LogLevel::Error, "Background demotion failed: cannot determine the current user".to_string())];
+        };
+
+        let mut demoted = Vec::new();
+
+        for (pid, exe_name) in snapshot {
+            if Self::is_exempt_process(pid, &exe_name, allowlist, protected_processes) {
+                continue;
+            }
+
+            let Some(process) = ProcessHandle::try_open_for_update(pid) else {
+                continue;
+            };
+
+            if process.owner_id().as_ref() != Some(&current_user_id) {
+                continue;
+            }
+
+            if let (Ok(current_priority), Ok(current_affinity)) = (process.priority(), process.affinity()) {
+                let mut changed = false;
+
+                if current_priority != priority && process.set_priority(priority, &[]).is_ok() {
+                    changed = true;
+                }
+
+                if let Some(affinity_mask) = affinity_mask {
+                    if current_affinity != affinity_mask && process.set_affinity(affinity_mask, &[]).is_ok() {
+                        changed = true;
+                    }
+                }
+
+                if changed {
+                    // Keep the settings from the first demotion, not the ones we applied
+                    self.demoted_processes.entry(pid).or_insert_with(|| DemotedProcess {
+                        pid,
+                        name: exe_name.clone(),
+                        original_priority: current_priority,
+                        original_affinity: current_affinity,
+                    });
+                    demoted.push(format!("{} (PID {})", exe_name, pid));
+                }
+            }
+        }
+
+        if demoted.is_empty() {
+            Vec::new()
+        } else {
+            vec![LogEntry::new(LogLevel::Demote, format!(
+                "Background demotion: demoted {} processes: {}",
+                demoted.len(),
+                demoted.join(", ")
+            ))]
+        }
+    }
+
+    /// Restore the original priority and affinity of every process lowered by background demotion
+    pub fn restore_demoted_processes(&mut self) -> Vec<LogEntry> {
+        if self.demoted_processes.is_empty() {
+            return Vec::new();
+        }
+
+        let restored = self.demoted_processes
+            .drain()
+            .filter(|(_, demoted)| Self::restore_process(demoted))
+            .count();
+
+        vec![LogEntry::new(LogLevel::Demote, format!("Background demotion ended: restored the original settings of {} processes.", restored))]
+    }
+
+    /// Get the processes currently lowered by background demotion
+    pub fn get_demoted_processes(&self) -> &HashMap<u32, DemotedProcess> {
+        &self.demoted_processes
+    }
+
+    /// Sample the CPU usage of every process, demoting those above the threshold for
+    /// longer than the policy allows and restoring demoted ones once they calm down
+    pub fn watch_cpu_hogs(&mut self, policy: &CpuHogPolicy, exemptions: &[String], protected_processes: &[String]) -> Vec<LogEntry> {
+        let snapshot = match self.snapshot_processes() {
+            Ok(snapshot) => snapshot,
+            Err(err) => return vec![LogEntry::new(LogLevel::Error, format!("CPU hog watchdog failed: {}", err))],
+        };
+
+        // Forget processes that have exited
+        let running_pids: std::collections::HashSet<u32> = snapshot.iter().map(|(pid, _)| *pid).collect();
+        self.cpu_samples.retain(|pid, _| running_pids.contains(pid));
+        self.cpu_hogs.retain(|pid, _| running_pids.contains(pid));
+
+        let now = Instant::now();
+        let mut logs = Vec::new();
+
+        for (pid, exe_name) in snapshot {
+            if Self::is_exempt_process(pid, &exe_name, exemptions, protected_processes) {
+                continue;
+            }
+
+            let Some(cpu_time) = ProcessHandle::open_for_query(pid).and_then(|process| process.cpu_time()) else {
+                continue;
+            };
+
+            // The first sample only establishes a baseline
+            let Some(sample) = self.cpu_samples.get_mut(&pid) else {
+                self.cpu_samples.insert(pid, CpuSample::new(cpu_time, now));
+                continue;
+            };
+
+            let Some(cpu_percent) = sample.record(cpu_time, now, policy.cpu_threshold_percent) else {
+                continue;
+            };
+
+            let over_for = sample.over_threshold_for(now);
+            let under_for = sample.under_threshold_for(now);
+            let is_demoted = self.cpu_hogs.contains_key(&pid);
+
+            if !is_demoted && over_for.is_some_and(|duration| duration >= policy.sustained) {
+                match Self::demote_process(pid, &exe_name, policy.priority, policy.affinity_mask) {
+                    Ok(demoted) => {
+                        let confinement = policy.affinity_mask
+                            .map(|mask| format!(" and confined to cores 0x{:X}", mask))
+                            .unwrap_or_default();
+                        logs.push(LogEntry::new(LogLevel::Watchdog, format!(
+                            "{} (PID {}) used {:.0}% CPU for {}s: priority lowered to {}{}.",
+                            exe_name,
+                            pid,
+                            cpu_percent,
+                            policy.sustained.as_secs(),
+                            policy.priority,
+                            confinement
+                        )));
+                        self.cpu_hogs.insert(pid, demoted);
+                    }
+                    Err(err) => {
+                        logs.push(LogEntry::new(LogLevel::Error, format!("CPU hog watchdog could not demote {} (PID {}): {}", exe_name, pid, err)));
+                        // Don't retry every cycle; wait for another full sustained period
+                        sample.over_threshold_since = Some(now);
+                    }
+                }
+            } else if is_demoted && under_for.is_some_and(|duration| duration >= policy.calm) {
+                if let Some(demoted) = self.cpu_hogs.remove(&pid) {
+                    if Self::restore_process(&demoted) {
+                        logs.push(LogEntry::new(LogLevel::Watchdog, format!(
+                            "{} (PID {}) calmed down: restored priority {} and affinity 0x{:X}.",
+                            demoted.name, pid, demoted.original_priority, demoted.original_affinity
+                        )));
+                    }
+                }
+            }
+        }
+
+        logs
+    }
+
+    /// Restore every process demoted by the CPU hog watchdog
+    pub fn restore_cpu_hogs(&mut self) -> Vec<LogEntry> {
+        self.cpu_samples.clear();
+
+        if self.cpu_hogs.is_empty() {
+            return Vec::new();
+        }
+
+        let restored = self.cpu_hogs
+            .drain()
+            .filter(|(_, demoted)| Self::restore_process(demoted))
+            .count();
+
+        vec![LogEntry::new(LogLevel::Watchdog, format!("CPU hog watchdog stopped: restored the original settings of {} processes.", restored))]
+    }
+
+    /// Sample the CPU usage of every process running under a real-time rule, demoting those
+    /// that keep a core fully busy for too long and restoring them once they calm down
+    pub fn guard_realtime_processes(&mut self, rules: &[ProcessRule], policy: &RealtimeGuardPolicy) -> Vec<LogEntry> {
+        let now = Instant::now();
+        let mut logs = Vec::new();
+        let mut guarded_pids = HashSet::new();
+
+        for rule in rules.iter().filter(|rule| rule.is_realtime()) {
+            let pids = match self.find_all_processes_by_name(&rule.name) {
+                Ok(pids) => pids,
+                Err(err) => {
+                    logs.push(LogEntry::new(LogLevel::Error, format!("Real-time guard failed for {}: {}", rule.name, err)));
+                    continue;
+                }
+            };
+
+            for pid in pids {
+                guarded_pids.insert(pid);
+
+                let Some(cpu_time) = ProcessHandle::open_for_query(pid).and_then(|process| process.cpu_time()) else {
+                    continue;
+                };
+
+                // The first sample only establishes a baseline
+                let Some(sample) = self.realtime_samples.get_mut(&pid) else {
+                    self.realtime_samples.insert(pid, CpuSample::new(cpu_time, now));
+                    continue;
+                };
+
+                let Some(cpu_percent) = sample.record(cpu_time, now, policy.cpu_threshold_percent) else {
+                    continue;
+                };
+
+                let over_for = sample.over_threshold_for(now);
+                let under_for = sample.under_threshold_for(now);
+                let is_demoted = self.realtime_demotions.contains_key(&pid);
+
+                if !is_demoted && over_for.is_some_and(|duration| duration >= policy.max_full_core) {
+                    let demoted_rule = rule.without_realtime(policy.priority);
+                    self.realtime_demotions.insert(pid, demoted_rule.clone());
+
+                    match self.monitor_and_reapply_settings_for_pid(pid, &demoted_rule) {
+                        Ok(changes) => logs.push(LogEntry::new(LogLevel::Realtime, format!(
+                            "{} (PID {}) kept a core at {:.0}% for {}s under a real-time rule: demoted ({}).",
+                            rule.name,
+                            pid,
+                            cpu_percent,
+                            policy.max_full_core.as_secs(),
+                            changes
+                        ))),
+                        Err(err) => {
+                            self.realtime_demotions.remove(&pid);
+                            logs.push(LogEntry::new(LogLevel::Error, format!("Real-time guard could not demote {} (PID {}): {}", rule.name, pid, err)));
+                        }
+                    }
+                } else if is_demoted && under_for.is_some_and(|duration| duration >= policy.calm) {
+                    self.realtime_demotions.remove(&pid);
+
+                    match self.monitor_and_reapply_settings_for_pid(pid, rule) {
+                        Ok(changes) => logs.push(LogEntry::new(LogLevel::Realtime, format!(
+                            "{} (PID {}) calmed down: real-time settings restored ({}).",
+                            rule.name, pid, changes
+                        ))),
+                        Err(err) => logs.push(LogEntry::new(LogLevel::Error, format!("Real-time guard could not restore {} (PID {}): {}", rule.name, pid, err))),
+                    }
+                }
+            }
+        }
+
+        // Forget processes that have exited or are no longer under a real-time rule
+        self.realtime_samples.retain(|pid, _| guarded_pids.contains(pid));
+        self.realtime_demotions.retain(|pid, _| guarded_pids.contains(pid));
+
+        logs
+    }
+
+    /// Stop guarding real-time processes; demoted ones keep their safe settings
+    pub fn release_realtime_guard(&mut self) -> Vec<LogEntry> {
+        self.realtime_samples.clear();
+
+        if self.realtime_demotions.is_empty() {
+            return Vec::new();
+        }
+
+        let demoted = self.realtime_demotions.drain().count();
+        vec![LogEntry::new(LogLevel::Realtime, format!("Real-time guard stopped: {} demoted processes keep their demoted settings.", demoted))]
+    }
+
+    /// Act on instances started beyond their rule's `max_instances`, newest first by start time,
+    /// and undo lowering or suspension once an instance is back within the limit
+    pub fn enforce_instance_limits(&mut self, rules: &[ProcessRule], protected_processes: &[String]) -> Vec<LogEntry> {
+        let mut logs = self.check_closing_instances();
+
+        for rule in rules {
+            let Some(limit) = rule.instance_limit else {
+                continue;
+            };
+
+            // Oldest first; instances whose start time can't be read count as newest
+            let mut instances: Vec<(Duration, u32)> = self.rule_instances
+                .get(&rule.name)
+                .map(|pids| pids.as_slice())
+                .unwrap_or_default()
+                .iter()
+                .map(|&pid| {
+                    let start_time = ProcessHandle::open_for_query(pid).and_then(|process| process.start_time());
+                    (start_time.unwrap_or(Duration::MAX), pid)
+                })
+                .collect();
+            instances.sort();
+
+            let allowed = (limit.max_instances as usize).min(instances.len());
+            let (within_limit, extra) = instances.split_at(allowed);
+
+            for &(_, pid) in within_limit {
+                match self.extra_instances.remove(&pid) {
+                    Some(InstanceLimitAction::LowerPriority) => {
+                        self.lowered_instances.remove(&pid);
+                        logs.push(LogEntry::new(LogLevel::Instance, format!("{} (PID {}) is within the instance limit again; rule settings restored.", rule.name, pid)));
+                    }
+                    Some(InstanceLimitAction::Suspend) => {
+                        let (resumed, errors) = self.resume_suspended(&[pid]);
+                        if !resumed.is_empty() {
+                            logs.push(LogEntry::new(LogLevel::Instance, format!("{} (PID {}) is within the instance limit again; resumed.", rule.name, pid)));
+                        }
+                        logs.extend(errors.into_iter().map(|err| LogEntry::new(LogLevel::Error, format!("Failed to resume {}", err))));
+                    }
+                    _ => {}
+                }
+            }
+
+            for &(_, pid) in extra {
+                if self.extra_instances.contains_key(&pid) {
+                    continue;
+                }
+                // Acted on once, failures included, so a stubborn instance isn't retried every pass
+                self.extra_instances.insert(pid, limit.action);
+
+                let over_limit = format!("{} has {} instances (max {})", rule.name, instances.len(), limit.max_instances);
+                let reason = format!("instance limit of {}", rule.name);
+
+                let result = match limit.action {
+                    InstanceLimitAction::Warn => Ok(format!("{}; PID {} is extra.", over_limit, pid)),
+                    InstanceLimitAction::LowerPriority => {
+                        self.lowered_instances.insert(pid, rule.without_realtime(Priority::Idle));
+                        Ok(format!("{}; PID {} lowered to Idle.", over_limit, pid))
+                    }
+                    InstanceLimitAction::Suspend => self
+                        .suspend_process(pid, false, &reason, protected_processes)
+                        .map(|_| format!("{}; PID {} suspended.", over_limit, pid)),
+                    InstanceLimitAction::TerminateNewest => {
+                        let options = TerminateOptions {
+                            graceful_timeout: Some(EXTRA_INSTANCE_CLOSE_TIMEOUT),
+                            include_tree: false,
+                            force: false,
+                        };
+                        // Forced on a later pass if it hasn't closed by then
+                        self.start_kill(pid, options, &format!("rule {}", rule.name), protected_processes).map(|termination| {
+                            self.closing_instances.push((rule.name.clone(), termination));
+                            format!("{}; PID {} asked to close.", over_limit, pid)
+                        })
+                    }
+                };
+
+                match result {
+                    Ok(message) => logs.push(LogEntry::new(LogLevel::Instance, message)),
+                    Err(err) => logs.push(LogEntry::new(LogLevel::Error, format!("{}; PID {} could not be limited: {}", over_limit, pid, err))),
+                }
+            }
+        }
+
+        // Forget instances that have exited
+        let running: HashSet<u32> = self.rule_instances.values().flatten().copied().collect();
+        self.extra_instances.retain(|pid, _| running.contains(pid));
+        self.lowered_instances.retain(|pid, _| running.contains(pid));
+
+        logs
+    }
+
+    /// Finish the terminations of extra instances that have exited, or outlived being forced
+    fn check_closing_instances(&mut self) -> Vec<LogEntry> {
+        let mut logs = Vec::new();
+
+        for (rule_name, mut termination) in std::mem::take(&mut self.closing_instances) {
+            if !termination.poll() {
+                self.closing_instances.push((rule_name, termination));
+                continue;
+            }
+
+            let pid = termination.pid;
+            logs.push(match self.finish_kill(termination) {
+                Ok(result) if result.survived.is_empty() => {
+                    LogEntry::new(LogLevel::Instance, format!("Extra instance of {} (PID {}) terminated.", rule_name, pid))
+                }
+                Ok(_) => LogEntry::new(LogLevel::Error, format!("Extra instance of {} (PID {}) survived termination.", rule_name, pid)),
+                Err(err) => LogEntry::new(LogLevel::Error, format!("Extra instance of {} (PID {}) could not be terminated: {}", rule_name, pid, err)),
+            });
+        }
+
+        logs
+    }
+
+    /// Stop limiting instances; lowered extra instances keep their lowered priority, and
+    /// extra instances asked to close are no longer forced
+    pub fn release_instance_limits(&mut self) -> Vec<LogEntry> {
+        self.extra_instances.clear();
+        let mut logs = Vec::new();
+
+        let closing: Vec<u32> = self.closing_instances.iter().map(|(_, termination)| termination.pid).collect();
+        for (_, termination) in std::mem::take(&mut self.closing_instances) {
+            let _ = self.finish_kill(termination);
+        }
+        if !closing.is_empty() {
+            logs.push(LogEntry::new(LogLevel::Instance, format!("Instance limits stopped: no longer waiting for PIDs {} to close.", format_pid_list(&closing))));
+        }
+
+        if !self.lowered_instances.is_empty() {
+            let lowered = self.lowered_instances.drain().count();
+            logs.push(LogEntry::new(LogLevel::Instance, format!("Instance limits stopped: {} lowered instances keep their lowered priority.", lowered)));
+        }
+
+        logs
+    }
+
+    /// Lower a single process's priority and optionally confine it, returning its previous settings
+    fn demote_process(pid: u32, exe_name: &str, priority: Priority, affinity_mask: Option<u64>) -> Result<DemotedProcess, ManagerError> {
+        let process = ProcessHandle::open_for_update(pid)?;
+
+        let original_priority = process.priority()?;
+        let original_affinity = process.affinity()?;
+
+        process.set_priority(priority, &[])?;
+
+        if let Some(affinity_mask) = affinity_mask {
+            if let Err(err) = process.set_affinity(affinity_mask, &[]) {
+                let _ = process.set_priority(original_priority, &[]);
+                return Err(err);
+            }
+        }
+
+        Ok(DemotedProcess {
+            pid,
+            name: exe_name.to_string(),
+            original_priority,
+            original_affinity,
+        })
+    }
+
+    /// Put back the settings a process had before it was demoted
+    fn restore_process(demoted: &DemotedProcess) -> bool {
+        // Processes that exited in the meantime have nothing to restore
+        let Some(process) = ProcessHandle::try_open_for_update(demoted.pid) else {
+            return false;
+        };
+
+        let priority_restored = process.set_priority(demoted.original_priority, &[]).is_ok();
+        let affinity_restored = process.set_affinity(demoted.original_affinity, &[]).is_ok();
+
+        priority_restored && affinity_restored
+    }
+
+    /// Create the managed cgroup subtree that rules with cgroup limits place their processes in
+    pub fn enable_cgroups(&mut self, tree: CgroupTree) -> Result<Vec<LogEntry>, ManagerError> {
+        let unavailable = tree.prepare()?;
+        self.cgroups = Some(tree);
+
+        if unavailable.is_empty() {
+            Ok(Vec::new())
+        } else {
+            Ok(vec![LogEntry::new(LogLevel::Cgroup, format!(
+                "cgroup controllers not available: {}. Limits that need them will fail.",
+                unavailable.join(", ")
+            ))])
+        }
+    }
+
+    /// Move managed processes back to their original cgroups and remove the managed subtree
+    pub fn release_cgroups(&mut self) -> Vec<LogEntry> {
+        let Some(mut tree) = self.cgroups.take() else {
+            return Vec::new();
+        };
+
+        let (moved, errors) = tree.cleanup();
+        let outcome = if errors.is_empty() { "Managed cgroups removed" } else { "Managed cgroups partly removed" };
+        let mut logs = vec![LogEntry::new(LogLevel::Cgroup, format!("{}; {} processes moved back to their original groups.", outcome, moved))];
+        logs.extend(errors.into_iter().map(|err| LogEntry::new(LogLevel::Error, format!("cgroup cleanup failed: {}", err))));
+        logs
+    }
+
+    /// PIDs of every descendant of a process
+    fn descendants_of(pid: u32) -> Vec<u32> {
+        let parents = platform::parent_pids();
+        let mut descendants = Vec::new();
+        let mut frontier = vec![pid];
+
+        while let Some(parent) = frontier.pop() {
+            for (&child, &child_parent) in &parents {
+                // Reused PIDs can make a stale parent link loop back, so each PID is visited once
+                if child_parent == parent && child != pid && !descendants.contains(&child) {
+                    descendants.push(child);
+                    frontier.push(child);
+                }
+            }
+        }
+
+        descendants
+    }
+
+    /// Suspend a process, and optionally all its descendants, returning the PIDs that were suspended
+    pub fn suspend_process(&mut self, pid: u32, include_tree: bool, reason: &str, protected_processes: &[String]) -> Result<Vec<u32>, ManagerError> {
+        let mut targets = vec![pid];
+        if include_tree {
+            targets.extend(Self::descendants_of(pid));
+        }
+
+        let mut suspended = Vec::new();
+        for target in targets {
+            if self.suspended_processes.contains_key(&target) {
+                continue;
+            }
+
+            let name = platform::process_name(target).unwrap_or_else(|| "Unknown".to_string());
+
+            // Descendants that are protected or can't be suspended are left running
+            let result = if Self::is_protected_process(target, &name, protected_processes) {
+                Err(ManagerError::protected_process(target, format!("{} (PID {}) is protected and cannot be suspended", name, target)))
+            } else {
+                ProcessHandle::open_for_suspend(target).and_then(|process| process.suspend())
+            };
+
+            match result {
+                Ok(()) => {
+                    self.suspended_processes.insert(target, SuspendedProcess {
+                        pid: target,
+                        name,
+                        reason: reason.to_string(),
+                        root_pid: pid,
+                        frozen_group: None,
+                    });
+                    suspended.push(target);
+                }
+                Err(err) if target == pid => return Err(err),
+                Err(_) => {}
+            }
+        }
+
+        Ok(suspended)
+    }
+
+    /// Resume a suspended process together with everything suspended alongside it
+    pub fn resume_process(&mut self, pid: u32) -> Result<Vec<u32>, ManagerError> {
+        let root_pid = self.suspended_processes
+            .get(&pid)
+            .map(|suspended| suspended.root_pid)
+            .ok_or_else(|| ManagerError::other(format!("PID {} was not suspended by the manager", pid)))?;
+
+        let members: Vec<u32> = self.suspended_processes
+            .values()
+            .filter(|suspended| suspended.root_pid == root_pid)
+            .map(|suspended| suspended.pid)
+            .collect();
+
+        let (resumed, mut errors) = self.resume_suspended(&members);
+        match errors.len() {
+            0 => Ok(resumed),
+            1 => Err(errors.remove(0)),
+            _ => Err(ManagerError::other(errors.iter().map(|err| err.to_string()).collect::<Vec<_>>().join("; "))),
+        }
+    }
+
+    /// Resume the given suspended processes, thawing frozen cgroups once each;
+    /// returns the resumed PIDs and any errors
+    fn resume_suspended(&mut self, pids: &[u32]) -> (Vec<u32>, Vec<ManagerError>) {
+        let mut resumed = Vec::new();
+        let mut errors = Vec::new();
+        let mut thawed_groups: Vec<String> = Vec::new();
+
+        for pid in pids {
+            let Some(suspended) = self.suspended_processes.remove(pid) else {
+                continue;
+            };
+
+            let result = match (&suspended.frozen_group, &self.cgroups) {
+                (Some(group), _) if thawed_groups.contains(group) => Ok(()),
+                (Some(group), Some(cgroups)) => {
+                    thawed_groups.push(group.clone());
+                    cgroups.set_frozen(group, false)
+                }
+                // Processes that exited while suspended have nothing to resume
+                _ => match ProcessHandle::open_for_suspend(suspended.pid) {
+                    Ok(process) => process.resume(),
+                    Err(_) => continue,
+                },
+            };
+
+            match result {
+                Ok(()) => resumed.push(suspended.pid),
+                Err(err) => errors.push(err.context(format_args!("{} (PID {})", suspended.name, suspended.pid))),
+            }
+        }
+
+        (resumed, errors)
+    }
+
+    /// Suspend the processes of rules whose trigger processes are running, and resume them
+    /// once none of the triggers are left
+    pub fn enforce_suspend_rules(&mut self, rules: &[ProcessRule], protected_processes: &[String]) -> Vec<LogEntry> {
+        let suspend_rules: Vec<&ProcessRule> = rules.iter().filter(|rule| !rule.suspend_while_running.is_empty()).collect();
+        if suspend_rules.is_empty() {
+            return Vec::new();
+        }
+
+        let snapshot = match self.snapshot_processes() {
+            Ok(snapshot) => snapshot,
+            Err(err) => return vec![LogEntry::new(LogLevel::Error, format!("Suspend rules failed: {}", err))],
+        };
+
+        let mut logs = Vec::new();
+
+        for rule in suspend_rules {
+            let reason = format!("rule {}", rule.name);
+            let trigger = rule.suspend_while_running
+                .iter()
+                .find(|trigger| snapshot.iter().any(|(_, exe_name)| exe_name.eq_ignore_ascii_case(trigger)));
+
+            let Some(trigger) = trigger else {
+                let pids: Vec<u32> = self.suspended_processes
+                    .values()
+                    .filter(|suspended| suspended.reason == reason)
+                    .map(|suspended| suspended.pid)
+                    .collect();
+                if pids.is_empty() {
+                    continue;
+                }
+
+                let (resumed, errors) = self.resume_suspended(&pids);
+                if !resumed.is_empty() {
+                    logs.push(LogEntry::new(LogLevel::Suspend, format!("{} resumed (PIDs: {}): triggers no longer running.", rule.name, format_pid_list(&resumed))));
+                }
+                logs.extend(errors.into_iter().map(|err| LogEntry::new(LogLevel::Error, format!("Failed to resume {}", err))));
+                continue;
+            };
+
+            let pids: Vec<u32> = snapshot
+                .iter()
+                .filter(|(pid, exe_name)| exe_name.eq_ignore_ascii_case(&rule.name) && !self.suspended_processes.contains_key(pid))
+                .map(|(pid, _)| *pid)
+                .collect();
+            if pids.is_empty() {
+                continue;
+            }
+
+            // A rule with its own cgroup is frozen as a whole, children included. Only processes
+            // confirmed to be in the group are frozen with it, e.g. not one that couldn't be moved
+            // there; those, and everything when freezing fails, are stopped one by one instead
+            let (in_group, pids): (Vec<u32>, Vec<u32>) = match (&rule.cgroup, &self.cgroups) {
+                (Some(_), Some(cgroups)) => pids.into_iter().partition(|pid| cgroups.contains(&rule.name, *pid)),
+                _ => (Vec::new(), pids),
+            };
+            let (frozen, pids) = match &self.cgroups {
+                Some(cgroups) if !in_group.is_empty() => match cgroups.set_frozen(&rule.name, true) {
+                    Ok(()) => (in_group, pids),
+                    Err(err) => {
+                        logs.push(LogEntry::new(LogLevel::Error, format!("Failed to freeze {}, stopping its processes instead: {}", rule.name, err)));
+                        (Vec::new(), in_group.into_iter().chain(pids).collect())
+                    }
+                },
+                _ => (Vec::new(), pids),
+            };
+
+            let mut suspended = Vec::new();
+            for pid in frozen {
+                self.suspended_processes.insert(pid, SuspendedProcess {
+                    pid,
+                    name: rule.name.clone(),
+                    reason: reason.clone(),
+                    root_pid: pid,
+                    frozen_group: Some(rule.name.clone()),
+                });
+                suspended.push(pid);
+            }
+            for pid in pids {
+                match self.suspend_process(pid, true, &reason, protected_processes) {
+                    Ok(pids) => suspended.extend(pids),
+                    Err(err) => logs.push(LogEntry::new(LogLevel::Error, format!("Failed to suspend {}: {}", rule.name, err))),
+                }
+            }
+
+            if !suspended.is_empty() {
+                logs.push(LogEntry::new(LogLevel::Suspend, format!("{} suspended while {} runs (PIDs: {}).", rule.name, trigger, format_pid_list(&suspended))));
+            }
+        }
+
+        logs
+    }
+
+    /// Resume every process the manager has suspended
+    pub fn resume_all_suspended(&mut self) -> Vec<LogEntry> {
+        if self.suspended_processes.is_empty() {
+            return Vec::new();
+        }
+
+        let pids: Vec<u32> = self.suspended_processes.keys().copied().collect();
+        let (resumed, errors) = self.resume_suspended(&pids);
+
+        let mut logs = vec![LogEntry::new(LogLevel::Suspend, format!("Resumed {} suspended processes.", resumed.len()))];
+        logs.extend(errors.into_iter().map(|err| LogEntry::new(LogLevel::Error, format!("Failed to resume {}", err))));
+        logs
+    }
+
+    pub fn get_suspended_processes(&self) -> &HashMap<u32, SuspendedProcess> {
+        &self.suspended_processes
+    }
+
+    /// Get all currently tracked process instances
+    pub fn get_tracked_processes(&self) -> &HashMap<u32, ProcessInstance> {
+        &self.tracked_processes
+    }
+
+    /// Terminate a process on behalf of a rule or user action and wait for it to exit;
+    /// see `start_kill`
+    pub fn kill_process(&mut self, pid: u32, options: TerminateOptions, requested_by: &str, protected_processes: &[String]) -> Result<TerminationResult, ManagerError> {
+        let mut termination = self.start_kill(pid, options, requested_by, protected_processes)?;
+        termination.wait();
+        self.finish_kill(termination)
+    }
+
+    /// Start terminating a process on behalf of a rule or user action. Protected processes are
+    /// always refused, and processes the manager doesn't track need `force`. The close requests,
+    /// or the forced termination when there is no graceful timeout, are sent right away; waiting
+    /// for the processes to exit is up to the returned termination, which doesn't need the manager.
+    /// Refusals are recorded in the kill audit log here, everything else by `finish_kill`
+    pub fn start_kill(&mut self, pid: u32, options: TerminateOptions, requested_by: &str, protected_processes: &[String]) -> Result<PendingTermination, ManagerError> {
+        let name = platform::process_name(pid).unwrap_or_else(|| "Unknown".to_string());
+
+        let processes = if Self::is_protected_process(pid, &name, protected_processes) {
+            Err(ManagerError::protected_process(pid, format!("{} (PID {}) is protected and cannot be terminated", name, pid)))
+        } else if !options.force && !self.tracked_processes.contains_key(&pid) {
+            Err(ManagerError::other(format!("{} (PID {}) is not tracked by the manager; terminating it needs force", name, pid)))
+        } else {
+            self.open_termination_targets(pid, options, protected_processes)
+        };
+
+        let mut termination = PendingTermination {
+            pid,
+            name,
+            requested_by: requested_by.to_string(),
+            force: options.force,
+            remaining: Vec::new(),
+            result: TerminationResult::default(),
+            deadline: Instant::now(),
+            forced: false,
+        };
+
+        match processes {
+            Ok(processes) => {
+                termination.remaining = processes;
+                match options.graceful_timeout {
+                    Some(timeout) => termination.request_close(timeout),
+                    None => termination.force(),
+                }
+                Ok(termination)
+            }
+            Err(err) => {
+                self.audit_termination(&termination, Some(&err));
+                Err(err)
+            }
+        }
+    }
+
+    /// Stop tracking the processes a termination ended and record its outcome in the kill audit log
+    pub fn finish_kill(&mut self, mut termination: PendingTermination) -> Result<TerminationResult, ManagerError> {
+        let still_running: Vec<u32> = termination.remaining.drain(..).map(|(target, _)| target).collect();
+        termination.result.survived.extend(still_running);
+
+        for target in &termination.result.exited {
+            self.tracked_processes.remove(target);
+            self.suspended_processes.remove(target);
+        }
+
+        let pid = termination.pid;
+        let result = &termination.result;
+        let error = (!result.signalled.contains(&pid) && !result.exited.contains(&pid))
+            .then(|| ManagerError::other(format!("Failed to terminate process PID {}", pid)));
+        self.audit_termination(&termination, error.as_ref());
+
+        match error {
+            Some(err) => Err(err),
+            None => Ok(termination.result),
+        }
+    }
+
+    /// Record a termination in the kill audit log, with the error that refused or failed it
+    fn audit_termination(&mut self, termination: &PendingTermination, error: Option<&ManagerError>) {
+        let result = &termination.result;
+        let outcome = match error {
+            None => format!(
+                "signalled [{}], exited [{}], survived [{}]",
+                format_pid_list(&result.signalled),
+                format_pid_list(&result.exited),
+                format_pid_list(&result.survived)
+            ),
+            Some(err) => format!("refused or failed: {}", err),
+        };
+        self.audit_kill(KillAuditEntry {
+            timestamp: Local::now(),
+            pid: termination.pid,
+            name: termination.name.clone(),
+            requested_by: termination.requested_by.clone(),
+            force: termination.force,
+            outcome,
+        });
+    }
+
+    /// Record a kill request in memory and append it to the audit file
+    fn audit_kill(&mut self, entry: KillAuditEntry) {
+        let line = format!(
+            "{} pid={} name={:?} requested_by={:?} force={} {}\n",
+            entry.timestamp.to_rfc3339(),
+            entry.pid,
+            entry.name,
+            entry.requested_by,
+            entry.force,
+            entry.outcome
+        );
+
+        // The in-memory record still holds the entry when the file can't be written
+        let written = self.kill_audit_path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::OpenOptions::new().create(true).append(true).open(&self.kill_audit_path))
+            .and_then(|mut file| file.write_all(line.as_bytes()));
+        if let Err(err) = written {
+            let err = ManagerError::os(&err, format_args!("Failed to write kill audit log {}", self.kill_audit_path.display()));
+            self.logs.push(LogEntry::new(LogLevel::Error, err.to_string()));
+        }
+
+        self.kill_audit.push_back(entry);
+        while self.kill_audit.len() > KILL_AUDIT_CAPACITY {
+            self.kill_audit.pop_front();
+        }
+    }
+
+    pub fn get_kill_audit(&self) -> Vec<KillAuditEntry> {
+        self.kill_audit.iter().cloned().collect()
+    }
+
+    /// Append kill requests to kill_audit.log in this directory from now on
+    pub fn set_kill_audit_directory(&mut self, directory: &Path) {
+        self.kill_audit_path = directory.join(KILL_AUDIT_LOG);
+    }
+
+    /// Open a process, and its descendants for a tree kill, for termination. Suspended targets
+    /// are resumed first, since a stopped process can't react to a close request
+    fn open_termination_targets(&mut self, pid: u32, options: TerminateOptions, protected_processes: &[String]) -> Result<Vec<(u32, ProcessHandle)>, ManagerError> {
+        // Descendants are collected up front, before their parent's exit reparents them
+        let mut targets = vec![pid];
+        if options.include_tree {
+            targets.extend(Self::descendants_of(pid));
+        }
+
+        let mut processes = Vec::new();
+        for target in targets {
+            // Protected descendants are left running
+            if target != pid {
+                let name = platform::process_name(target).unwrap_or_default();
+                if Self::is_protected_process(target, &name, protected_processes) {
+                    continue;
+                }
+            }
+
+            match ProcessHandle::open_for_terminate(target) {
+                Ok(process) => processes.push((target, process)),
+                Err(err) if target == pid => return Err(err),
+                Err(_) => {}
+            }
+        }
+
+        let suspended: Vec<u32> = processes
+            .iter()
+            .map(|(target, _)| *target)
+            .filter(|target| self.suspended_processes.contains_key(target))
+            .collect();
+        self.resume_suspended(&suspended);
+
+        Ok(processes)
+    }
+
+    /// Get the current and last applied settings of every thread of a process
+    pub fn get_thread_details(&self, pid: u32) -> Result<Vec<ThreadDetails>, ManagerError> {
+        let process = ProcessHandle::open_for_query(pid)
+            .ok_or_else(|| ManagerError::process_exited(pid, format!("Failed to open process PID {} for information", pid)))?;
+
+        let tracked_threads = self.tracked_processes.get(&pid).map(|instance| &instance.threads);
+
+        Ok(process
+            .threads()
+            .into_iter()
+            .map(|(tid, name)| {
+                let tracked = tracked_threads.and_then(|threads| threads.get(&tid));
+                ThreadDetails {
+                    tid,
+                    name,
+                    current_affinity: process.thread_affinity(tid).ok(),
+                    current_priority: process.thread_priority(tid).ok(),
+                    matched_rule: tracked.map(|thread| thread.rule.clone()),
+                    last_applied_affinity: tracked.and_then(|thread| thread.last_applied_affinity),
+                    last_applied_priority: tracked.and_then(|thread| thread.last_applied_priority),
+                }
+            })
+            .collect())
+    }
+
+    /// Get detailed process information by PID
+    pub fn get_process_details(&self, pid: u32) -> Result<ProcessDetails, ManagerError> {
+        let process = ProcessHandle::open_for_query(pid)
+            .ok_or_else(|| ManagerError::process_exited(pid, format!("Failed to open process PID {} for information", pid)))?;
+
+        // Get process name
+        let process_name = platform::process_name(pid).unwrap_or_else(|| "Unknown".to_string());
+
+        let tracked_info = self.tracked_processes.get(&pid);
+        let cgroup_root = self.cgroups
+            .as_ref()
+            .map(|tree| tree.root())
+            .unwrap_or(Path::new(cgroup::DEFAULT_ROOT));
+
+        Ok(ProcessDetails {
+            pid,
+            name: process_name,
+            current_priority: process.priority().ok(),
+            current_affinity: process.affinity().ok(),
+            current_scheduling: process.scheduling().ok(),
+            current_io_priority: process.io_priority().ok(),
+            last_applied_priority: tracked_info.and_then(|t| t.last_applied_priority),
+            last_applied_affinity: tracked_info.and_then(|t| t.last_applied_affinity),
+            last_applied_scheduling: tracked_info.and_then(|t| t.last_applied_scheduling),
+            last_applied_io_priority: tracked_info.and_then(|t| t.last_applied_io_priority),
+            cgroup: cgroup::current_group(pid),
+            memory_limits: cgroup::memory_limits(cgroup_root, pid),
+            current_oom_score_adj: process.oom_score_adj().ok(),
+            last_applied_oom_score_adj: tracked_info.and_then(|t| t.last_applied_oom_score_adj),
+            numa_usage: process.numa_usage().ok(),
+            last_applied_numa: tracked_info.and_then(|t| t.last_applied_numa),
+            is_tracked: tracked_info.is_some(),
+        })
+    }
+}
+
+pub fn format_pid_list(pids: &[u32]) -> String {
+    pids.iter()
+        .map(|pid| pid.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Default for ProcessManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn retry_in(manager: &ProcessManager, pid: u32) -> Duration {
+        manager.tracked_processes[&pid].retry_at.unwrap().saturating_duration_since(Instant::now())
+    }
+
+    #[test]
+    fn forgets_processes_that_exited() {
+        let mut manager = ProcessManager::new();
+        manager.track_process(42);
+
+        let reported = manager.record_failure(42, ManagerError::process_exited(42, "gone"));
+
+        assert!(reported.is_none());
+        assert!(!manager.tracked_processes.contains_key(&42));
+    }
+
+    #[test]
+    fn marks_access_denied_unmanageable_and_reports_it_once() {
+        let mut manager = ProcessManager::new();
+        manager.track_process(42);
+
+        let first = manager.record_failure(42, ManagerError::access_denied(42, "denied"));
+        let second = manager.record_failure(42, ManagerError::access_denied(42, "denied"));
+
+        assert!(first.is_some_and(|err| err.needs_elevation()));
+        assert!(second.is_none());
+        let instance = &manager.tracked_processes[&42];
+        assert_eq!(instance.unmanageable.as_deref(), Some("denied"));
+        assert_eq!(instance.failures, 2);
+        assert!(instance.retry_pending());
+        assert!(retry_in(&manager, 42) > RETRY_MAX_DELAY);
+    }
+
+    #[test]
+    fn backs_off_other_failures_up_to_the_maximum() {
+        assert_eq!(retry_delay(1), RETRY_BASE_DELAY);
+        assert_eq!(retry_delay(2), RETRY_BASE_DELAY * 2);
+        assert_eq!(retry_delay(3), RETRY_BASE_DELAY * 4);
+        assert_eq!(retry_delay(10), RETRY_MAX_DELAY);
+        assert_eq!(retry_delay(u32::MAX), RETRY_MAX_DELAY);
+
+        let mut manager = ProcessManager::new();
+        manager.track_process(42);
+        for _ in 0..3 {
+            assert!(manager.record_failure(42, ManagerError::other("failed")).is_some());
+        }
+        assert_eq!(manager.tracked_processes[&42].failures, 3);
+        let delay = retry_in(&manager, 42);
+        assert!(delay <= RETRY_BASE_DELAY * 4 && delay > RETRY_BASE_DELAY * 2);
+        assert!(manager.tracked_processes[&42].unmanageable.is_none());
+    }
+
+    #[test]
+    fn success_clears_the_retry_state() {
+        let mut manager = ProcessManager::new();
+        manager.track_process(42);
+        manager.record_failure(42, ManagerError::access_denied(42, "denied"));
+
+        manager.record_success(42);
+
+        let instance = &manager.tracked_processes[&42];
+        assert_eq!(instance.failures, 0);
+        assert!(instance.unmanageable.is_none());
+        assert!(!instance.retry_pending());
+    }
+
+    #[test]
+    fn releases_the_reservation_only_to_the_process_it_restricted() {
+        // This test process stands in for a restricted one; its mask is given back unchanged
+        let pid = std::process::id();
+        let process = ProcessHandle::open_for_update(pid).unwrap();
+        let original_affinity = process.affinity().unwrap();
+        let start_time = process.start_time();
+        assert!(start_time.is_some());
+        let mut manager = ProcessManager::new();
+
+        let reused = start_time.map(|start_time| start_time + Duration::from_secs(1));
+        manager.reservation_originals.insert(pid, ReservedProcess { original_affinity, start_time: reused });
+        let logs = manager.release_core_reservation();
+        assert!(logs[0].message.ends_with("of 0 processes."), "{}", logs[0].message);
+
+        manager.reservation_originals.insert(pid, ReservedProcess { original_affinity, start_time });
+        let logs = manager.release_core_reservation();
+        assert!(logs[0].message.ends_with("of 1 processes."), "{}", logs[0].message);
+        assert!(manager.reservation_originals.is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn legacy_mode_emits_found_and_exited_events() {
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        let pid = child.id();
+        let mut manager = ProcessManager::new();
+        let rule = ProcessRule::new("sleep", ProcessManager::get_system_affinity_mask(), Priority::Normal);
+        let check = |manager: &mut ProcessManager| {
+            manager.check_and_manage_process(&rule);
+            manager.take_events()
+        };
+
+        let found = EngineEvent::ProcessFound { rule: "sleep".to_string(), pid };
+        assert!(check(&mut manager).contains(&found));
+        assert!(!check(&mut manager).contains(&found));
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert!(check(&mut manager).contains(&EngineEvent::ProcessExited { rule: "sleep".to_string(), pid }));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn legacy_mode_records_instances_for_the_instance_limit() {
+        let mut older = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        let mut newer = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        let mut rule = ProcessRule::new("sleep", ProcessManager::get_system_affinity_mask(), Priority::Normal);
+        rule.instance_limit = Some(InstanceLimit { max_instances: 1, action: InstanceLimitAction::Warn });
+        let mut manager = ProcessManager::new();
+
+        manager.check_and_manage_process(&rule);
+        let logs = manager.enforce_instance_limits(std::slice::from_ref(&rule), &[]);
+
+        let extra = format!("PID {} is extra.", newer.id());
+        assert!(logs.iter().any(|entry| entry.message.ends_with(&extra)), "{:?}", logs);
+        for child in [&mut older, &mut newer] {
+            child.kill().unwrap();
+            child.wait().unwrap();
+        }
+    }
+
+    fn audit_entry(pid: u32) -> KillAuditEntry {
+        KillAuditEntry {
+            timestamp: Local::now(),
+            pid,
+            name: "game.exe".to_string(),
+            requested_by: "user action".to_string(),
+            force: false,
+            outcome: "refused or failed: not tracked".to_string(),
+        }
+    }
+
+    #[test]
+    fn appends_kill_requests_to_the_audit_file_and_keeps_the_newest_in_memory() {
+        let directory = tempfile::TempDir::new().unwrap();
+        let mut manager = ProcessManager::new();
+        manager.set_kill_audit_directory(&directory.path().join("logs"));
+
+        for pid in 0..KILL_AUDIT_CAPACITY as u32 + 5 {
+            manager.audit_kill(audit_entry(pid));
+        }
+
+        let audit = manager.get_kill_audit();
+        assert_eq!(audit.len(), KILL_AUDIT_CAPACITY);
+        assert_eq!(audit[0].pid, 5);
+        let file = fs::read_to_string(directory.path().join("logs").join(KILL_AUDIT_LOG)).unwrap();
+        assert_eq!(file.lines().count(), KILL_AUDIT_CAPACITY + 5);
+        assert!(manager.take_logs().is_empty());
+    }
+
+    #[test]
+    fn reports_an_audit_file_that_cant_be_written() {
+        let directory = tempfile::TempDir::new().unwrap();
+        let not_a_directory = directory.path().join("file");
+        fs::write(&not_a_directory, "").unwrap();
+        let mut manager = ProcessManager::new();
+        manager.set_kill_audit_directory(&not_a_directory);
+
+        manager.audit_kill(audit_entry(42));
+
+        let logs = manager.take_logs();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].level, LogLevel::Error);
+        assert!(logs[0].message.starts_with("Failed to write kill audit log"), "{}", logs[0].message);
+        assert_eq!(manager.get_kill_audit().len(), 1);
+    }
+}
@@ -0,0 +1,261 @@
+use serde::de::DeserializeOwned;
+use serde::{Serialize, Deserialize};
+use serde_json::{json, Value};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+use crate::config::{BackgroundDemotionConfig, Config, CpuHogWatchdogConfig, ProcessConfig, RealtimeGuardConfig};
+use crate::engine::{Engine, DEFAULT_GRACEFUL_TIMEOUT_SECONDS};
+use crate::error::ManagerError;
+use crate::log_files::ExportFormat;
+use crate::logs::LogQuery;
+use crate::priority::Priority;
+use crate::process_manager::{ProcessManager, TerminateOptions};
+
+// JSON-RPC 2.0 error codes
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+// Application errors, such as a rejected rule or a process that can't be opened;
+// their `data` holds the ManagerError
+pub const SERVER_ERROR: i64 = -32000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcRequest {
+    pub jsonrpc: String,
+    #[serde(default)]
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcResponse {
+    pub jsonrpc: String,
+    pub id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl RpcRequest {
+    pub fn new(id: u64, method: &str, params: Value) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id: json!(id),
+            method: method.to_string(),
+            params,
+        }
+    }
+}
+
+impl RpcResponse {
+    pub fn new(id: Value, result: Result<Value, RpcError>) -> Self {
+        let (result, error) = match result {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+        Self { jsonrpc: "2.0".to_string(), id, result, error }
+    }
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into(), data: None }
+    }
+}
+
+impl From<ManagerError> for RpcError {
+    fn from(err: ManagerError) -> Self {
+        Self {
+            code: SERVER_ERROR,
+            message: err.to_string(),
+            data: serde_json::to_value(&err).ok(),
+        }
+    }
+}
+
+impl From<RpcError> for ManagerError {
+    /// The error a method failed with; protocol errors mean the backend doesn't understand the request
+    fn from(err: RpcError) -> Self {
+        if let Some(error) = err.data.and_then(|data| serde_json::from_value(data).ok()) {
+            return error;
+        }
+        match err.code {
+            INVALID_PARAMS => ManagerError::invalid_config(err.message),
+            SERVER_ERROR => ManagerError::other(err.message),
+            _ => ManagerError::backend_unavailable(err.message),
+        }
+    }
+}
+
+/// Where a request comes from; anything on the daemon's socket is another program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    InProcess,
+    Socket,
+}
+
+/// Handle one request line, returning the response line
+pub fn handle_line(engine: &Engine, origin: Origin, line: &str) -> String {
+    let response = match serde_json::from_str::<RpcRequest>(line) {
+        Ok(request) if request.jsonrpc != "2.0" => {
+            RpcResponse::new(request.id, Err(RpcError::new(INVALID_REQUEST, "Only JSON-RPC 2.0 is supported")))
+        }
+        Ok(request) => {
+            let result = dispatch(engine, origin, &request.method, &request.params);
+            RpcResponse::new(request.id, result)
+        }
+        Err(err) => RpcResponse::new(Value::Null, Err(RpcError::new(PARSE_ERROR, err.to_string()))),
+    };
+
+    serde_json::to_string(&response).unwrap_or_default()
+}
+
+/// A named parameter; missing parameters read as null, which optional parameters accept
+fn param<T: DeserializeOwned>(params: &Value, name: &str) -> Result<T, RpcError> {
+    serde_json::from_value(params.get(name).cloned().unwrap_or(Value::Null))
+        .map_err(|e| RpcError::new(INVALID_PARAMS, format!("Parameter '{}': {}", name, e)))
+}
+
+/// Socket clients may only name a file in the log directory, not write anywhere the daemon can
+fn export_path(origin: Origin, path: Option<PathBuf>) -> Result<Option<PathBuf>, ManagerError> {
+    match path {
+        Some(path) if origin == Origin::Socket && !is_file_name(&path) => Err(ManagerError::invalid_config(format!(
+            "Exports requested over the socket go to the log directory; '{}' must be a file name", path.display()
+        ))),
+        path => Ok(path),
+    }
+}
+
+fn is_file_name(path: &Path) -> bool {
+    let mut components = path.components();
+    matches!((components.next(), components.next()), (Some(Component::Normal(_)), None))
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(|e| RpcError::new(SERVER_ERROR, e.to_string()))
+}
+
+/// Run a method against the engine. Method and parameter names match the Tauri commands,
+/// so the GUI can forward its calls unchanged to a local engine or to the daemon
+pub fn dispatch(engine: &Engine, origin: Origin, method: &str, params: &Value) -> Result<Value, RpcError> {
+    match method {
+        "get_system_info" => Ok(json!({
+            "cpu_count": ProcessManager::get_system_cpu_count(),
+            "affinity_mask": format!("0x{:X}", ProcessManager::get_system_affinity_mask()),
+            "is_admin": ProcessManager::is_running_as_administrator()
+        })),
+        "get_config" => to_value(engine.config()),
+        "save_config_cmd" => to_value(engine.save_config(param::<Config>(params, "config")?)?),
+        "start_monitoring" => to_value(engine.start()?),
+        "stop_monitoring" => {
+            engine.stop();
+            Ok(Value::Null)
+        }
+        "get_monitoring_status" => to_value(engine.is_running()),
+        "get_logs" => to_value(engine.logs.lock().unwrap().entries()),
+        "get_logs_since" => to_value(engine.logs_since(param(params, "seq")?)),
+        "query_logs" => to_value(engine.query_logs(&param::<LogQuery>(params, "query")?)),
+        "export_logs" => to_value(engine.export_logs(
+            param(params, "since")?,
+            param(params, "until")?,
+            param::<ExportFormat>(params, "format")?,
+            export_path(origin, param(params, "path")?)?,
+        )?),
+        "clear_logs" => {
+            engine.logs.lock().unwrap().clear();
+            Ok(Value::Null)
+        }
+        "get_tracked_processes" => to_value(engine.tracked_processes()),
+        "add_process_config" => to_value(engine.add_process_config(
+            param(params, "name")?,
+            param(params, "coreSelections")?,
+            param::<Priority>(params, "priorityClass")?,
+        )?),
+        "remove_process_config" => to_value(engine.remove_process_config(&param::<String>(params, "name")?)?),
+        "update_process_config" => to_value(engine.update_process_config(
+            &param::<String>(params, "name")?,
+            param(params, "coreSelections")?,
+            param(params, "priorityClass")?,
+            param(params, "enabled")?,
+            param(params, "exclusiveCores")?,
+        )?),
+        "update_process_rule" => to_value(engine.update_process_rule(param::<ProcessConfig>(params, "processConfig")?)?),
+        "get_process_configs" => to_value(engine.config.lock().unwrap().processes.clone()),
+        "kill_process" => {
+            let graceful: Option<bool> = param(params, "graceful")?;
+            let timeout_seconds: Option<u64> = param(params, "timeoutSeconds")?;
+            let options = TerminateOptions {
+                graceful_timeout: graceful
+                    .unwrap_or(true)
+                    .then(|| Duration::from_secs(timeout_seconds.unwrap_or(DEFAULT_GRACEFUL_TIMEOUT_SECONDS))),
+                include_tree: param::<Option<bool>>(params, "includeTree")?.unwrap_or(false),
+                force: param::<Option<bool>>(params, "force")?.unwrap_or(false),
+            };
+            to_value(engine.kill_process(param(params, "pid")?, options)?)
+        }
+        "get_kill_audit" => to_value(engine.process_manager.lock().unwrap().get_kill_audit()),
+        "suspend_process" => to_value(engine.suspend_process(
+            param(params, "pid")?,
+            param::<Option<bool>>(params, "includeTree")?.unwrap_or(false),
+        )?),
+        "resume_process" => to_value(engine.resume_process(param(params, "pid")?)?),
+        "get_suspended_processes" => to_value(
+            engine.process_manager.lock().unwrap().get_suspended_processes().values().cloned().collect::<Vec<_>>(),
+        ),
+        "get_process_details" => Ok(engine.process_details(param(params, "pid")?)?),
+        "get_thread_details" => to_value(engine.process_manager.lock().unwrap().get_thread_details(param(params, "pid")?)?),
+        "preview_core_reservation" => to_value(engine.preview_core_reservation()?),
+        "update_background_demotion" => to_value(engine.update_background_demotion(BackgroundDemotionConfig {
+            enabled: param(params, "enabled")?,
+            allowlist: param(params, "allowlist")?,
+            priority_class: param(params, "priorityClass")?,
+            core_selections: param(params, "coreSelections")?,
+        })?),
+        "update_cpu_hog_watchdog" => to_value(engine.update_cpu_hog_watchdog(param::<CpuHogWatchdogConfig>(params, "watchdog")?)?),
+        "update_realtime_guard" => to_value(engine.update_realtime_guard(param::<RealtimeGuardConfig>(params, "guard")?)?),
+        "get_demoted_processes" => to_value(
+            engine.process_manager.lock().unwrap().get_demoted_processes().values().cloned().collect::<Vec<_>>(),
+        ),
+        _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("Unknown method '{}'", method))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manager_errors_survive_the_round_trip() {
+        let err = ManagerError::access_denied(42, "Access denied to PID 42");
+        let rpc_error = RpcError::from(err.clone());
+        assert_eq!(rpc_error.code, SERVER_ERROR);
+        assert_eq!(ManagerError::from(rpc_error), err);
+
+        // Errors from a backend that sends no data are classified by their code
+        assert_eq!(ManagerError::from(RpcError::new(METHOD_NOT_FOUND, "Unknown method")), ManagerError::backend_unavailable("Unknown method"));
+        assert_eq!(ManagerError::from(RpcError::new(INVALID_PARAMS, "Parameter 'pid'")), ManagerError::invalid_config("Parameter 'pid'"));
+    }
+
+    #[test]
+    fn socket_exports_only_take_file_names() {
+        assert_eq!(export_path(Origin::Socket, None).unwrap(), None);
+        assert_eq!(export_path(Origin::Socket, Some("logs.csv".into())).unwrap(), Some(PathBuf::from("logs.csv")));
+        for path in ["/tmp/logs.csv", "../logs.csv", "sub/logs.csv", ".."] {
+            assert!(export_path(Origin::Socket, Some(path.into())).is_err(), "{}", path);
+        }
+        assert_eq!(export_path(Origin::InProcess, Some("/tmp/logs.csv".into())).unwrap(), Some(PathBuf::from("/tmp/logs.csv")));
+    }
+}
@@ -0,0 +1,108 @@
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Tells a loop to stop; waiting on it wakes up as soon as it is cancelled
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    state: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        let (cancelled, wake) = &*self.state;
+        *cancelled.lock().unwrap() = true;
+        wake.notify_all();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.state.0.lock().unwrap()
+    }
+
+    /// Sleep for up to `timeout`, returning early with true if cancelled meanwhile
+    pub fn wait(&self, timeout: Duration) -> bool {
+        let (cancelled, wake) = &*self.state;
+        let guard = cancelled.lock().unwrap();
+        let (guard, _) = wake.wait_timeout_while(guard, timeout, |cancelled| !*cancelled).unwrap();
+        *guard
+    }
+}
+
+/// Owns the monitoring thread: at most one runs, and stopping waits for it to finish
+/// releasing what it changed, so a restart never overlaps the previous loop
+#[derive(Default)]
+pub struct Supervisor {
+    running: Option<(CancellationToken, JoinHandle<()>)>,
+}
+
+impl Supervisor {
+    /// Whether a loop is running and hasn't been told to stop
+    pub fn is_running(&self) -> bool {
+        self.running
+            .as_ref()
+            .is_some_and(|(token, handle)| !token.is_cancelled() && !handle.is_finished())
+    }
+
+    /// Run a loop on its own thread, after the previous one has finished. If the loop panics,
+    /// `on_panic` gets the panic message as soon as it happens
+    pub fn spawn(
+        &mut self,
+        run: impl FnOnce(CancellationToken) + Send + 'static,
+        on_panic: impl FnOnce(String) + Send + 'static,
+    ) {
+        self.stop();
+
+        let token = CancellationToken::default();
+        let thread_token = token.clone();
+        let handle = thread::spawn(move || {
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| run(thread_token))) {
+                on_panic(panic_message(payload.as_ref()));
+            }
+        });
+        self.running = Some((token, handle));
+    }
+
+    /// Cancel the loop and wait for it to finish
+    pub fn stop(&mut self) {
+        let Some((token, handle)) = self.running.take() else {
+            return;
+        };
+        token.cancel();
+        // A panic in the loop was already passed to `on_panic`
+        let _ = handle.join();
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn reports_a_panicking_loop_and_can_start_again() {
+        let mut supervisor = Supervisor::default();
+        let (sender, receiver) = mpsc::channel();
+
+        let panic_sender = sender.clone();
+        supervisor.spawn(|_| panic!("lost the process list"), move |message| panic_sender.send(message).unwrap());
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)).unwrap(), "lost the process list");
+
+        supervisor.spawn(|token| {
+            token.wait(Duration::from_secs(5));
+        }, move |message| sender.send(message).unwrap());
+        assert!(supervisor.is_running());
+        supervisor.stop();
+        assert!(!supervisor.is_running());
+        assert!(receiver.try_recv().is_err());
+    }
+}
@@ -0,0 +1,590 @@
+// Command-line client for the process manager, for scripts and sessions without the GUI.
+// Every command reads config.toml from the working directory, like the GUI does.
+
+use std::env;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Local, NaiveDate};
+use serde_json::json;
+
+use pp_manager_core::config::{self, Config};
+use pp_manager_core::engine::{Engine, DEFAULT_GRACEFUL_TIMEOUT_SECONDS};
+use pp_manager_core::error::ManagerError;
+use pp_manager_core::platform;
+use pp_manager_core::log_files::{self, ExportFormat};
+use pp_manager_core::logs::LogEntry;
+use pp_manager_core::process_manager::{format_pid_list, ProcessManager, ProcessRule, TerminateOptions};
+
+const USAGE: &str = "Usage: ppm [--json] <command>
+
+Commands:
+  list                      Processes matched by rules, with their current settings
+  apply <rule|pid>          Apply a rule once to all its processes, or to a single process
+  watch                     Run the monitoring loop in the foreground until Ctrl+C
+  kill <pid> [--tree] [--now] [--timeout <seconds>] [--force]
+                            Ask a process to close, then force it after the timeout
+  config validate           Check config.toml and the rules it defines
+  config show               Print the effective configuration
+  daemon [--socket <path>] [--start]
+                            Serve the manager to other clients over a local socket,
+                            optionally starting monitoring right away
+  call <method> [<json-params>] [--socket <path>]
+                            Call a method on a running daemon and print its result
+  events [--socket <path>]  Print a running daemon's events as they happen
+  logs export [--since <time>] [--until <time>] [--format csv|json] [--output <path>]
+                            Export a time range of the log files; times are RFC 3339
+                            or a date, e.g. 2024-05-01T09:00:00+02:00 or 2024-05-01
+
+--json prints machine-readable output instead of text.";
+
+enum Command {
+    List,
+    Apply(String),
+    Watch,
+    Kill {
+        pid: u32,
+        include_tree: bool,
+        now: bool,
+        timeout_seconds: u64,
+        force: bool,
+    },
+    ConfigValidate,
+    ConfigShow,
+    Daemon {
+        socket: Option<PathBuf>,
+        start: bool,
+    },
+    Call {
+        method: String,
+        params: serde_json::Value,
+        socket: Option<PathBuf>,
+    },
+    Events {
+        socket: Option<PathBuf>,
+    },
+    LogsExport {
+        since: Option<DateTime<Local>>,
+        until: Option<DateTime<Local>>,
+        format: ExportFormat,
+        output: Option<PathBuf>,
+    },
+}
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let json = take_flag(&mut args, "--json");
+
+    let Some(command) = parse_command(args) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let result = match command {
+        Command::List => list(json),
+        Command::Apply(target) => apply(&target, json),
+        Command::Watch => watch(json),
+        Command::Kill { pid, include_tree, now, timeout_seconds, force } => {
+            let options = TerminateOptions {
+                graceful_timeout: (!now).then(|| Duration::from_secs(timeout_seconds)),
+                include_tree,
+                force,
+            };
+            kill(pid, options, json)
+        }
+        Command::ConfigValidate => validate_config(json),
+        Command::ConfigShow => show_config(json),
+        Command::Daemon { socket, start } => daemon(socket, start),
+        Command::Call { method, params, socket } => call(&method, params, socket),
+        Command::Events { socket } => events(socket, json),
+        Command::LogsExport { since, until, format, output } => export_logs(since, until, format, output, json),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            if json {
+                println!("{}", json!({ "error": err }));
+            } else {
+                eprintln!("ppm: {}", err);
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Remove an option and its value from the arguments; None inside means the value is missing
+fn take_option(args: &mut Vec<String>, option: &str) -> Option<Option<String>> {
+    let index = args.iter().position(|arg| arg == option)?;
+    if index + 1 >= args.len() {
+        return Some(None);
+    }
+    let value = args.remove(index + 1);
+    args.remove(index);
+    Some(Some(value))
+}
+
+/// Remove a flag from the arguments, returning whether it was present
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let before = args.len();
+    args.retain(|arg| arg != flag);
+    args.len() != before
+}
+
+fn parse_command(mut args: Vec<String>) -> Option<Command> {
+    if args.is_empty() {
+        return None;
+    }
+
+    let name = args.remove(0);
+    match (name.as_str(), args.as_slice()) {
+        ("list", []) => Some(Command::List),
+        ("apply", [target]) => Some(Command::Apply(target.clone())),
+        ("watch", []) => Some(Command::Watch),
+        ("kill", _) => {
+            let include_tree = take_flag(&mut args, "--tree");
+            let now = take_flag(&mut args, "--now");
+            let force = take_flag(&mut args, "--force");
+
+            let timeout_seconds = match take_option(&mut args, "--timeout") {
+                Some(value) => value?.parse().ok()?,
+                None => DEFAULT_GRACEFUL_TIMEOUT_SECONDS,
+            };
+
+            match args.as_slice() {
+                [pid] => Some(Command::Kill { pid: pid.parse().ok()?, include_tree, now, timeout_seconds, force }),
+                _ => None,
+            }
+        }
+        ("config", [subcommand]) if subcommand == "validate" => Some(Command::ConfigValidate),
+        ("config", [subcommand]) if subcommand == "show" => Some(Command::ConfigShow),
+        ("daemon", _) => {
+            let start = take_flag(&mut args, "--start");
+            let socket = match take_option(&mut args, "--socket") {
+                Some(value) => Some(PathBuf::from(value?)),
+                None => None,
+            };
+            args.is_empty().then_some(Command::Daemon { socket, start })
+        }
+        ("call", _) => {
+            let socket = match take_option(&mut args, "--socket") {
+                Some(value) => Some(PathBuf::from(value?)),
+                None => None,
+            };
+            match args.as_slice() {
+                [method] => Some(Command::Call { method: method.clone(), params: json!({}), socket }),
+                [method, params] => Some(Command::Call { method: method.clone(), params: serde_json::from_str(params).ok()?, socket }),
+                _ => None,
+            }
+        }
+        ("logs", [subcommand, ..]) if subcommand == "export" => {
+            args.remove(0);
+            let since = match take_option(&mut args, "--since") {
+                Some(value) => Some(parse_time(&value?, false)?),
+                None => None,
+            };
+            let until = match take_option(&mut args, "--until") {
+                Some(value) => Some(parse_time(&value?, true)?),
+                None => None,
+            };
+            let format = match take_option(&mut args, "--format") {
+                Some(value) => serde_json::from_value(json!(value?)).ok()?,
+                None => ExportFormat::Csv,
+            };
+            let output = match take_option(&mut args, "--output") {
+                Some(value) => Some(PathBuf::from(value?)),
+                None => None,
+            };
+            args.is_empty().then_some(Command::LogsExport { since, until, format, output })
+        }
+        ("events", _) => {
+            let socket = match take_option(&mut args, "--socket") {
+                Some(value) => Some(PathBuf::from(value?)),
+                None => None,
+            };
+            args.is_empty().then_some(Command::Events { socket })
+        }
+        _ => None,
+    }
+}
+
+/// An RFC 3339 time, or a local date taken as its start, or its end for `end_of_day`
+fn parse_time(value: &str, end_of_day: bool) -> Option<DateTime<Local>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Local));
+    }
+
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+    let time = if end_of_day { date.and_hms_milli_opt(23, 59, 59, 999) } else { date.and_hms_opt(0, 0, 0) };
+    time?.and_local_timezone(Local).earliest()
+}
+
+fn list(json: bool) -> Result<(), ManagerError> {
+    let config = config::load_config()?;
+    let process_manager = ProcessManager::new();
+
+    let mut rows = Vec::new();
+    for process_config in &config.processes {
+        for pid in process_manager.find_all_processes_by_name(&process_config.name)? {
+            // Processes that exit between the snapshot and the query are skipped
+            if let Ok(details) = process_manager.get_process_details(pid) {
+                rows.push((process_config.name.clone(), process_config.enabled, details));
+            }
+        }
+    }
+
+    if json {
+        let rows: Vec<serde_json::Value> = rows
+            .into_iter()
+            .map(|(rule, enabled, details)| {
+                let mut row = serde_json::to_value(details).unwrap_or_default();
+                row["rule"] = json!(rule);
+                row["rule_enabled"] = json!(enabled);
+                row
+            })
+            .collect();
+        println!("{}", serde_json::Value::Array(rows));
+        return Ok(());
+    }
+
+    if rows.is_empty() {
+        println!("No processes match the configured rules.");
+        return Ok(());
+    }
+
+    println!("{:<8} {:<24} {:<12} {:<18} {:<16}", "PID", "RULE", "PRIORITY", "AFFINITY", "I/O PRIORITY");
+    for (rule, enabled, details) in rows {
+        let rule = if enabled { rule } else { format!("{} (disabled)", rule) };
+        println!(
+            "{:<8} {:<24} {:<12} {:<18} {:<16}",
+            details.pid,
+            rule,
+            details.current_priority.map_or("?".to_string(), |priority| format!("{:?}", priority)),
+            details.current_affinity.map_or("?".to_string(), |affinity| format!("0x{:X}", affinity)),
+            details.current_io_priority.map_or("-".to_string(), |io_priority| io_priority.to_string()),
+        );
+    }
+    Ok(())
+}
+
+fn apply(target: &str, json: bool) -> Result<(), ManagerError> {
+    let config = config::load_config()?;
+    let rules = config.enabled_rules().map_err(ManagerError::invalid_config)?;
+    let mut process_manager = ProcessManager::new();
+
+    let targets: Vec<(u32, &ProcessRule)> = match target.parse::<u32>() {
+        Ok(pid) => {
+            let name = platform::process_name(pid).ok_or_else(|| ManagerError::process_exited(pid, format!("No process with PID {}", pid)))?;
+            let rule = rules
+                .iter()
+                .find(|rule| rule.name.eq_ignore_ascii_case(&name))
+                .ok_or_else(|| ManagerError::invalid_config(format!("No enabled rule matches {} (PID {})", name, pid)))?;
+            vec![(pid, rule)]
+        }
+        Err(_) => {
+            let rule = rules
+                .iter()
+                .find(|rule| rule.name.eq_ignore_ascii_case(target))
+                .ok_or_else(|| ManagerError::invalid_config(format!("No enabled rule named '{}'", target)))?;
+            process_manager
+                .find_all_processes_by_name(&rule.name)?
+                .into_iter()
+                .map(|pid| (pid, rule))
+                .collect()
+        }
+    };
+
+    if targets.is_empty() && !json {
+        println!("No running processes match {}.", target);
+    }
+
+    let mut results = Vec::new();
+    let mut failures = 0;
+    for (pid, rule) in targets {
+        let result = process_manager.apply_rule(pid, rule);
+        failures += usize::from(result.is_err());
+
+        if json {
+            results.push(match &result {
+                Ok(changes) => json!({ "pid": pid, "rule": rule.name, "changes": changes }),
+                Err(err) => json!({ "pid": pid, "rule": rule.name, "error": err }),
+            });
+        } else {
+            match &result {
+                Ok(changes) if changes.is_empty() => println!("PID {} ({}): already up to date", pid, rule.name),
+                Ok(changes) => println!("PID {} ({}): {}", pid, rule.name, changes),
+                Err(err) => println!("PID {} ({}): failed: {}", pid, rule.name, err),
+            }
+            // Limits that need a running monitor aren't part of a one-off apply
+            if rule.cgroup.is_some() || rule.instance_limit.is_some() || !rule.suspend_while_running.is_empty() {
+                println!("  cgroup limits, instance limits and suspend rules only apply under `ppm watch`");
+            }
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::Value::Array(results));
+    }
+
+    if failures > 0 {
+        return Err(ManagerError::other(format!("{} processes could not be updated", failures)));
+    }
+    Ok(())
+}
+
+fn watch(json: bool) -> Result<(), ManagerError> {
+    let engine = Engine::with_config(config::load_config()?, Vec::new());
+
+    platform::catch_interrupts();
+    engine.start()?;
+
+    let mut seq = 0;
+    while !platform::interrupted() {
+        seq = print_new_logs(&engine, seq, json);
+        thread::sleep(Duration::from_millis(250));
+    }
+
+    // Returns once the loop has released what it changed
+    engine.stop();
+    print_new_logs(&engine, seq, json);
+    Ok(())
+}
+
+/// Print log entries after the one numbered `seq`, returning the number of the last one printed
+fn print_new_logs(engine: &Engine, seq: u64, json: bool) -> u64 {
+    let entries = engine.logs_since(seq);
+    for entry in &entries {
+        print_log(entry, json);
+    }
+    entries.last().map_or(seq, |entry| entry.seq)
+}
+
+fn print_log(entry: &LogEntry, json: bool) {
+    if json {
+        println!("{}", serde_json::to_string(entry).unwrap_or_default());
+    } else {
+        println!("{} {:<9} {}", entry.timestamp.format("%Y-%m-%d %H:%M:%S"), entry.level, entry.message);
+    }
+}
+
+fn kill(pid: u32, options: TerminateOptions, json: bool) -> Result<(), ManagerError> {
+    let config = config::load_config()?;
+    let mut process_manager = ProcessManager::new();
+    process_manager.set_kill_audit_directory(&log_files::log_directory(&config.log_files));
+
+    // A process matched by an enabled rule is one the manager would track while monitoring
+    let name = platform::process_name(pid).unwrap_or_default();
+    if config.processes.iter().any(|p| p.enabled && p.name.eq_ignore_ascii_case(&name)) {
+        process_manager.track_process(pid);
+    }
+
+    let result = process_manager.kill_process(pid, options, "command line", &config.protected_processes);
+    // Such as a kill audit log that couldn't be written
+    for entry in process_manager.take_logs() {
+        eprintln!("ppm: {}", entry.message);
+    }
+    let result = result?;
+
+    if json {
+        println!("{}", serde_json::to_string(&result).unwrap_or_default());
+    } else {
+        println!("Signalled: {}", format_pid_list(&result.signalled));
+        println!("Exited:    {}", format_pid_list(&result.exited));
+        if !result.survived.is_empty() {
+            println!("Survived:  {}", format_pid_list(&result.survived));
+        }
+    }
+
+    if !result.survived.is_empty() {
+        return Err(ManagerError::other(format!("PIDs {} are still running", format_pid_list(&result.survived))));
+    }
+    Ok(())
+}
+
+fn validate_config(json: bool) -> Result<(), ManagerError> {
+    // Loading already rejects invalid rules; enabled ones must also be supported here
+    let config = config::load_config()?;
+    let enabled = config.enabled_rules().map_err(ManagerError::invalid_config)?.len();
+
+    if json {
+        println!("{}", json!({ "valid": true, "rules": config.processes.len(), "enabled_rules": enabled }));
+    } else {
+        println!("{} is valid: {} rules, {} enabled.", config::CONFIG_PATH, config.processes.len(), enabled);
+    }
+    Ok(())
+}
+
+fn show_config(json: bool) -> Result<(), ManagerError> {
+    let config: Config = config::load_config()?;
+
+    let output = if json {
+        serde_json::to_string_pretty(&config).map_err(|e| ManagerError::other(e.to_string()))?
+    } else {
+        toml::to_string(&config).map_err(|e| ManagerError::other(e.to_string()))?
+    };
+    println!("{}", output);
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn daemon(socket: Option<PathBuf>, start: bool) -> Result<(), ManagerError> {
+    use pp_manager_core::daemon;
+
+    let socket = socket.unwrap_or_else(daemon::default_socket_path);
+    let engine = Engine::new();
+    if start {
+        engine.start()?;
+    }
+
+    eprintln!("ppm: serving on {} until interrupted", socket.display());
+    // Stops the engine, releasing what it changed, before returning
+    daemon::serve(&engine, &socket)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn daemon(_socket: Option<PathBuf>, _start: bool) -> Result<(), ManagerError> {
+    Err(ManagerError::backend_unavailable("Daemon mode is only supported on Linux"))
+}
+
+#[cfg(target_os = "linux")]
+fn call(method: &str, params: serde_json::Value, socket: Option<PathBuf>) -> Result<(), ManagerError> {
+    use pp_manager_core::daemon::{self, Client};
+
+    let socket = socket.unwrap_or_else(daemon::default_socket_path);
+    let result = Client::connect(&socket)?.call(method, params)?;
+    println!("{}", serde_json::to_string_pretty(&result).map_err(|e| ManagerError::other(e.to_string()))?);
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn call(_method: &str, _params: serde_json::Value, _socket: Option<PathBuf>) -> Result<(), ManagerError> {
+    Err(ManagerError::backend_unavailable("Daemon mode is only supported on Linux"))
+}
+
+#[cfg(target_os = "linux")]
+fn events(socket: Option<PathBuf>, json: bool) -> Result<(), ManagerError> {
+    use pp_manager_core::daemon::{self, Client};
+    use pp_manager_core::events::EngineEvent;
+
+    let socket = socket.unwrap_or_else(daemon::default_socket_path);
+    for event in Client::connect(&socket)?.subscribe()? {
+        if json {
+            println!("{}", serde_json::to_string(&event).unwrap_or_default());
+            continue;
+        }
+
+        match event {
+            EngineEvent::ProcessFound { rule, pid } => println!("found     {} (PID {})", rule, pid),
+            EngineEvent::SettingsReapplied { rule, pid, changes } => println!("reapplied {} (PID {}): {}", rule, pid, changes),
+            EngineEvent::ProcessExited { rule, pid } => println!("exited    {} (PID {})", rule, pid),
+            EngineEvent::Error { message } => println!("error     {}", message),
+            EngineEvent::MonitoringStarted => println!("monitoring started"),
+            EngineEvent::MonitoringStopped => println!("monitoring stopped"),
+        }
+    }
+
+    Err(ManagerError::backend_unavailable("The daemon closed the connection"))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn events(_socket: Option<PathBuf>, _json: bool) -> Result<(), ManagerError> {
+    Err(ManagerError::backend_unavailable("Daemon mode is only supported on Linux"))
+}
+
+fn export_logs(
+    since: Option<DateTime<Local>>,
+    until: Option<DateTime<Local>>,
+    format: ExportFormat,
+    output: Option<PathBuf>,
+    json: bool,
+) -> Result<(), ManagerError> {
+    // Read straight from the log files, so this works whether or not anything is running
+    let config = config::load_config()?;
+    let directory = log_files::log_directory(&config.log_files);
+    let entries = log_files::read_range(&directory, since, until)?;
+
+    let extension = match format {
+        ExportFormat::Csv => "csv",
+        ExportFormat::Json => "json",
+    };
+    let output = output.unwrap_or_else(|| PathBuf::from(format!("pp_manager-logs.{}", extension)));
+    log_files::export(&entries, format, &output)?;
+
+    if json {
+        println!("{}", json!({ "path": output.display().to_string(), "entries": entries.len() }));
+    } else {
+        println!("Exported {} entries from {} to {}", entries.len(), directory.display(), output.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Timelike;
+
+    fn parse(args: &str) -> Option<Command> {
+        parse_command(args.split_whitespace().map(String::from).collect())
+    }
+
+    #[test]
+    fn parses_kill_options_in_any_order() {
+        assert!(matches!(
+            parse("kill --now 42 --timeout 3 --tree"),
+            Some(Command::Kill { pid: 42, include_tree: true, now: true, timeout_seconds: 3, force: false })
+        ));
+        assert!(matches!(
+            parse("kill 42"),
+            Some(Command::Kill { pid: 42, include_tree: false, now: false, timeout_seconds: DEFAULT_GRACEFUL_TIMEOUT_SECONDS, force: false })
+        ));
+        assert!(parse("kill --timeout 42").is_none());
+        assert!(parse("kill 42 43").is_none());
+        assert!(parse("kill abc").is_none());
+    }
+
+    #[test]
+    fn parses_calls_with_optional_params_and_socket() {
+        let Some(Command::Call { method, params, socket }) = parse(r#"call kill_process {"pid":1} --socket /tmp/s.sock"#) else {
+            panic!("call not parsed");
+        };
+        assert_eq!(method, "kill_process");
+        assert_eq!(params, json!({"pid": 1}));
+        assert_eq!(socket, Some(PathBuf::from("/tmp/s.sock")));
+
+        assert!(matches!(parse("call get_config"), Some(Command::Call { socket: None, .. })));
+        assert!(parse("call get_config not-json").is_none());
+        assert!(parse("daemon --socket").is_none());
+    }
+
+    #[test]
+    fn rejects_unknown_commands_and_extra_arguments() {
+        assert!(parse("").is_none());
+        assert!(parse("list extra").is_none());
+        assert!(parse("config edit").is_none());
+        assert!(parse("logs export --format xml").is_none());
+        assert!(parse("events --verbose").is_none());
+        assert!(matches!(parse("config validate"), Some(Command::ConfigValidate)));
+    }
+
+    #[test]
+    fn exports_logs_between_whole_days() {
+        let Some(Command::LogsExport { since, until, format, output }) = parse("logs export --since 2024-05-01 --until 2024-05-02 --format json") else {
+            panic!("logs export not parsed");
+        };
+        let (since, until) = (since.unwrap(), until.unwrap());
+        assert_eq!((since.date_naive().to_string(), since.hour(), since.minute()), ("2024-05-01".to_string(), 0, 0));
+        assert_eq!((until.date_naive().to_string(), until.hour(), until.minute()), ("2024-05-02".to_string(), 23, 59));
+        assert_eq!(format, ExportFormat::Json);
+        assert_eq!(output, None);
+    }
+
+    #[test]
+    fn parses_rfc_3339_times_and_dates() {
+        let time = parse_time("2024-05-01T09:00:00+02:00", false).unwrap();
+        assert_eq!(time, DateTime::parse_from_rfc3339("2024-05-01T07:00:00Z").unwrap());
+        assert!(parse_time("2024-05-01", true).unwrap() > parse_time("2024-05-01", false).unwrap());
+        assert!(parse_time("May 1st", false).is_none());
+    }
+}